@@ -0,0 +1,259 @@
+//! Conversions between `Bits` and integers or bytes. `Bits` hold unsigned
+//! or two's complement numbers, index 0 being the least significant bit.
+//! Numbers that don't fit and bits that are X or Z give errors instead of
+//! being truncated or guessed.
+
+use super::{Bits, LogicValue, PackedBits, LV};
+
+fn no_fit(value: impl std::fmt::Display, width: usize) -> String {
+    format!("{} doesn't fit into {} bits", value, width)
+}
+
+impl Bits {
+    /// Known bits from little endian words, bits past `width` are dropped
+    /// and missing ones are 0.
+    pub(super) fn from_le_words(width: usize, words: &[u64]) -> Bits {
+        Bits {
+            value: PackedBits::from_levels(width, words),
+        }
+    }
+
+    /// The bits as little endian words, fails if any bit is X or Z.
+    pub(super) fn to_le_words(&self) -> Result<Vec<u64>, String> {
+        self.value
+            .levels()
+            .map_err(|i| format!("Bit {} is {}", i, self.get(i)))
+    }
+
+    pub fn from_u128(width: usize, value: u128) -> Result<Bits, String> {
+        if width < 128 && value >> width != 0 {
+            return Err(no_fit(value, width));
+        }
+        Ok(Bits::from_le_words(
+            width,
+            &[value as u64, (value >> 64) as u64],
+        ))
+    }
+
+    /// Two's complement, sign extended to `width`.
+    pub fn from_i128(width: usize, value: i128) -> Result<Bits, String> {
+        let fits = match width {
+            0 => value == 0,
+            1..=127 => matches!(value >> (width - 1), 0 | -1),
+            _ => true,
+        };
+        if !fits {
+            return Err(no_fit(value, width));
+        }
+        let sign = if value < 0 { u64::MAX } else { 0 };
+        let words: Vec<u64> = [value as u64, (value >> 64) as u64]
+            .into_iter()
+            .chain(std::iter::repeat(sign))
+            .take(width.div_ceil(64).max(2))
+            .collect();
+        Ok(Bits::from_le_words(width, &words))
+    }
+
+    pub fn from_u64(width: usize, value: u64) -> Result<Bits, String> {
+        Bits::from_u128(width, value.into())
+    }
+
+    pub fn from_i64(width: usize, value: i64) -> Result<Bits, String> {
+        Bits::from_i128(width, value.into())
+    }
+
+    pub fn to_u128(&self) -> Result<u128, String> {
+        let words = self.to_le_words()?;
+        if words.iter().skip(2).any(|word| *word != 0) {
+            return Err(format!(
+                "{} doesn't fit into a u128",
+                self.to_literal(super::Radix::Hex)
+            ));
+        }
+        let word = |i: usize| words.get(i).copied().unwrap_or(0) as u128;
+        Ok(word(0) | word(1) << 64)
+    }
+
+    pub fn to_i128(&self) -> Result<i128, String> {
+        let words = self.to_le_words()?;
+        let n = self.len();
+        if n == 0 {
+            return Ok(0);
+        }
+        let negative = self.get(n - 1) == LV::H;
+        // Everything from bit 127 up must repeat the sign
+        if (127..n).any(|i| (self.get(i) == LV::H) != negative) {
+            return Err(format!(
+                "{} doesn't fit into an i128",
+                self.to_literal(super::Radix::Hex)
+            ));
+        }
+        let word = |i: usize| words.get(i).copied().unwrap_or(0) as u128;
+        let mut value = word(0) | word(1) << 64;
+        if negative && n < 128 {
+            value |= u128::MAX << n;
+        }
+        Ok(value as i128)
+    }
+
+    pub fn to_u64(&self) -> Result<u64, String> {
+        let value = self.to_u128()?;
+        u64::try_from(value).map_err(|_| format!("{} doesn't fit into a u64", value))
+    }
+
+    pub fn to_i64(&self) -> Result<i64, String> {
+        let value = self.to_i128()?;
+        i64::try_from(value).map_err(|_| format!("{} doesn't fit into an i64", value))
+    }
+
+    /// Bytes in little endian order, the bits past `width` must be 0.
+    pub fn from_le_bytes(width: usize, bytes: &[u8]) -> Result<Bits, String> {
+        let words: Vec<u64> = bytes
+            .chunks(8)
+            .map(|chunk| {
+                let mut word = [0; 8];
+                word[..chunk.len()].copy_from_slice(chunk);
+                u64::from_le_bytes(word)
+            })
+            .collect();
+        let all = Bits::from_le_words(bytes.len() * 8, &words);
+        if (width..all.len()).any(|i| all.get(i) == LV::H) {
+            return Err(no_fit(format!("{} bytes", bytes.len()), width));
+        }
+        Ok(Bits::from_le_words(width, &words))
+    }
+
+    /// Little endian bytes, the last one padded with 0 bits.
+    pub fn to_le_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut bytes: Vec<u8> = self
+            .to_le_words()?
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        bytes.truncate(self.len().div_ceil(8));
+        Ok(bytes)
+    }
+
+    /// Bytes in big endian order, the bits past `width` must be 0.
+    pub fn from_be_bytes(width: usize, bytes: &[u8]) -> Result<Bits, String> {
+        let reversed: Vec<u8> = bytes.iter().rev().copied().collect();
+        Bits::from_le_bytes(width, &reversed)
+    }
+
+    /// Big endian bytes, the first one padded with 0 bits.
+    pub fn to_be_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut bytes = self.to_le_bytes()?;
+        bytes.reverse();
+        Ok(bytes)
+    }
+}
+
+impl<V: LogicValue> Bits<V> {
+    fn extend_with(&self, width: usize, fill: V) -> Self {
+        assert!(
+            width >= self.len(),
+            "Can't extend {} bits to {} bits",
+            self.len(),
+            width
+        );
+        (0..self.len())
+            .map(|i| self.get(i))
+            .chain((self.len()..width).map(|_| fill.clone()))
+            .collect()
+    }
+
+    /// Widens to `width` bits with 0 on top, panics if `width` is smaller.
+    pub fn zero_extend(&self, width: usize) -> Self {
+        self.extend_with(width, V::from_bool(false))
+    }
+
+    /// Widens to `width` bits repeating the most significant bit, whatever
+    /// its value, panics if `width` is smaller. Empty bits extend with 0.
+    pub fn sign_extend(&self, width: usize) -> Self {
+        let sign = match self.len() {
+            0 => V::from_bool(false),
+            n => self.get(n - 1),
+        };
+        self.extend_with(width, sign)
+    }
+
+    /// The lowest `width` bits, panics if there are fewer.
+    pub fn truncate(&self, width: usize) -> Self {
+        assert!(
+            width <= self.len(),
+            "Can't truncate {} bits to {} bits",
+            self.len(),
+            width
+        );
+        (0..width).map(|i| self.get(i)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integers() {
+        let b = Bits::from_u64(12, 0xabc).unwrap();
+        assert_eq!(b, Bits::new(12).set_num(0xabc));
+        assert_eq!(b.to_u64(), Ok(0xabc));
+        assert_eq!(b.to_i64(), Ok(0xabc - 0x1000));
+        assert!(Bits::from_u64(12, 0x1000).is_err());
+
+        let minus = Bits::from_i64(200, -3).unwrap();
+        assert_eq!(minus.to_i128(), Ok(-3));
+        assert!(minus.to_u128().is_err());
+        assert_eq!(Bits::from_i64(4, -8).unwrap().to_i64(), Ok(-8));
+        assert!(Bits::from_i64(4, 8).is_err());
+        assert!(Bits::from_i64(4, -9).is_err());
+
+        let wide = Bits::from_u128(128, u128::MAX).unwrap();
+        assert_eq!(wide.to_u128(), Ok(u128::MAX));
+        assert_eq!(wide.to_i128(), Ok(-1));
+        assert!(wide.to_u64().is_err());
+    }
+
+    #[test]
+    fn test_unknown_bits_fail() {
+        let mut b = Bits::from_u64(8, 5).unwrap();
+        b.set(6, LV::Z);
+        assert_eq!(b.to_u64(), Err("Bit 6 is Z".to_string()));
+        assert!(b.to_le_bytes().is_err());
+    }
+
+    #[test]
+    fn test_bytes() {
+        let b = Bits::from_le_bytes(12, &[0x34, 0x02]).unwrap();
+        assert_eq!(b.to_u64(), Ok(0x234));
+        assert_eq!(b.to_le_bytes(), Ok(vec![0x34, 0x02]));
+        assert!(Bits::from_le_bytes(12, &[0x34, 0x12]).is_err());
+
+        let bytes: Vec<u8> = (1..=20).collect();
+        let wide = Bits::from_be_bytes(160, &bytes).unwrap();
+        assert_eq!(wide.to_be_bytes(), Ok(bytes));
+        assert_eq!(wide.truncate(16).to_u64(), Ok(0x1314));
+        assert_eq!(Bits::from_be_bytes(12, &[0x02, 0x34]), Ok(b));
+    }
+
+    #[test]
+    fn test_extend_truncate() {
+        let b = Bits::from_i64(4, -3).unwrap();
+        assert_eq!(b.sign_extend(200).to_i128(), Ok(-3));
+        assert_eq!(b.zero_extend(200).to_u128(), Ok(13));
+        assert_eq!(b.sign_extend(200).truncate(4), b);
+        assert_eq!(Bits::new(3).set_num(-1).sign_extend(70).to_i64(), Ok(-1));
+
+        let mut unknown = Bits::from_u64(2, 1).unwrap();
+        unknown.set(1, LV::X);
+        assert_eq!(unknown.sign_extend(4).to_string(), "1XXX");
+        assert_eq!(unknown.zero_extend(4).to_string(), "1X00");
+    }
+
+    #[test]
+    fn test_set_num_wide() {
+        let b = Bits::new(130).set_num(-2);
+        assert_eq!(b, Bits::from_i64(130, -2).unwrap());
+        assert_eq!(Bits::new(4).set_num(0x1f), Bits::from_u64(4, 0xf).unwrap());
+    }
+}
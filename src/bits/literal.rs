@@ -0,0 +1,333 @@
+//! Verilog style literals such as `8'b10xz_0101`, `16'hAB_CD` or `4'd9`,
+//! and printing `Bits` in binary, octal, decimal or hex.
+
+use std::{fmt, str::FromStr};
+
+use super::{Bits, LV};
+
+/// The base of a literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Binary,
+    Octal,
+    Decimal,
+    Hex,
+}
+
+impl Radix {
+    fn from_letter(c: char) -> Option<Radix> {
+        match c.to_ascii_lowercase() {
+            'b' => Some(Radix::Binary),
+            'o' => Some(Radix::Octal),
+            'd' => Some(Radix::Decimal),
+            'h' => Some(Radix::Hex),
+            _ => None,
+        }
+    }
+
+    fn letter(self) -> char {
+        match self {
+            Radix::Binary => 'b',
+            Radix::Octal => 'o',
+            Radix::Decimal => 'd',
+            Radix::Hex => 'h',
+        }
+    }
+
+    /// Bits per digit, `None` for decimal.
+    fn digit_bits(self) -> Option<usize> {
+        match self {
+            Radix::Binary => Some(1),
+            Radix::Octal => Some(3),
+            Radix::Decimal => None,
+            Radix::Hex => Some(4),
+        }
+    }
+}
+
+/// Width of literals without a size, as in Verilog.
+const UNSIZED_WIDTH: usize = 32;
+
+/// Digits of a power of two radix, least significant bit first. `x`, `z`
+/// and `?` make all bits of their digit X or Z.
+fn parse_digits(digits: &str, digit_bits: usize) -> Option<Vec<LV>> {
+    let mut bits = Vec::new();
+    for c in digits.chars().rev() {
+        let digit = match c.to_ascii_lowercase() {
+            'x' => vec![LV::X; digit_bits],
+            'z' | '?' => vec![LV::Z; digit_bits],
+            c => {
+                let value = c.to_digit(1 << digit_bits)?;
+                (0..digit_bits)
+                    .map(|i| LV::from(value >> i & 1 == 1))
+                    .collect()
+            }
+        };
+        bits.extend(digit);
+    }
+    Some(bits)
+}
+
+/// A decimal number in as few bits as it needs, or a single `x` or `z`.
+fn parse_decimal(digits: &str) -> Option<Vec<LV>> {
+    match digits.to_ascii_lowercase().as_str() {
+        "x" => return Some(vec![LV::X]),
+        "z" | "?" => return Some(vec![LV::Z]),
+        _ => {}
+    }
+    if digits.chars().all(|c| c == '0') {
+        return Some(vec![LV::L]);
+    }
+    let mut words: Vec<u64> = Vec::new();
+    for c in digits.chars() {
+        let mut carry = c.to_digit(10)? as u128;
+        for word in words.iter_mut() {
+            let next = *word as u128 * 10 + carry;
+            *word = next as u64;
+            carry = next >> 64;
+        }
+        if carry != 0 {
+            words.push(carry as u64);
+        }
+    }
+    let top = words.last()?;
+    let used = words.len() * 64 - top.leading_zeros() as usize;
+    let all = Bits::from_le_words(used, &words);
+    Some((0..used).map(|i| all.get(i)).collect())
+}
+
+/// Extends like Verilog, with X or Z if the leftmost digit was unknown and
+/// with 0 otherwise. Cutting off anything but 0 or the unknown fill fails.
+fn fit(mut bits: Vec<LV>, width: usize) -> Option<Bits> {
+    let fill = match bits.last() {
+        Some(LV::X) => LV::X,
+        Some(LV::Z) => LV::Z,
+        _ => LV::L,
+    };
+    if bits.len() > width {
+        let top = bits[width - 1].clone();
+        if bits[width..]
+            .iter()
+            .any(|lv| *lv != LV::L && (*lv != top || top == LV::H))
+        {
+            return None;
+        }
+    }
+    bits.resize(width, fill);
+    Some(bits.into_iter().collect())
+}
+
+impl FromStr for Bits {
+    type Err = String;
+
+    /// Reads `[-][size]'[s]<radix><digits>` or a plain decimal number,
+    /// with `_` allowed between digits. Literals without a size are at
+    /// least 32 bits wide, the `s` for signed is accepted and ignored.
+    fn from_str(s: &str) -> Result<Bits, String> {
+        let invalid = || format!("Invalid literal {}", s);
+        let (negative, literal) = match s.trim().strip_prefix('-') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, s.trim()),
+        };
+        let (size, radix, digits) = match literal.split_once('\'') {
+            Some((size, rest)) => {
+                let rest = rest.strip_prefix(['s', 'S']).unwrap_or(rest);
+                let mut chars = rest.chars();
+                let radix = chars
+                    .next()
+                    .and_then(Radix::from_letter)
+                    .ok_or_else(invalid)?;
+                (size.trim(), radix, chars.as_str().trim())
+            }
+            None => ("", Radix::Decimal, literal),
+        };
+        if digits.is_empty() || digits.starts_with('_') {
+            return Err(invalid());
+        }
+        let digits: String = digits.chars().filter(|c| *c != '_').collect();
+        let bits = match radix.digit_bits() {
+            Some(digit_bits) => parse_digits(&digits, digit_bits),
+            None => parse_decimal(&digits),
+        }
+        .ok_or_else(invalid)?;
+        let width = if size.is_empty() {
+            bits.len().max(UNSIZED_WIDTH)
+        } else {
+            match size.parse::<usize>() {
+                Ok(width) if width > 0 => width,
+                _ => return Err(format!("Invalid size in literal {}", s)),
+            }
+        };
+        let bits = fit(bits, width).ok_or(format!("{} doesn't fit into {} bits", s, width))?;
+        if negative {
+            bits.to_le_words()
+                .map_err(|_| format!("Can't negate the unknown literal {}", s))?;
+            return Ok(bits.negate());
+        }
+        Ok(bits)
+    }
+}
+
+/// `x` or `z` if all bits are X or Z, `X` or `Z` if only some are, like
+/// Verilog's `$display`.
+fn unknown_char(bits: &[LV]) -> Option<char> {
+    let all = |lv: LV| bits.iter().all(|b| *b == lv);
+    if all(LV::X) {
+        Some('x')
+    } else if all(LV::Z) {
+        Some('z')
+    } else if bits.contains(&LV::X) {
+        Some('X')
+    } else if bits.contains(&LV::Z) {
+        Some('Z')
+    } else {
+        None
+    }
+}
+
+/// Decimal digits of little endian words.
+fn decimal(mut words: Vec<u64>) -> String {
+    let mut digits = Vec::new();
+    loop {
+        let mut remainder = 0u128;
+        for word in words.iter_mut().rev() {
+            let current = remainder << 64 | *word as u128;
+            *word = (current / 10) as u64;
+            remainder = current % 10;
+        }
+        digits.push(char::from(b'0' + remainder as u8));
+        if words.iter().all(|word| *word == 0) {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+impl Bits {
+    /// The digits in `radix`, most significant first. A digit whose bits
+    /// are all X or all Z prints as `x` or `z`, one with only some unknown
+    /// bits as `X` or `Z`. Decimal numbers with unknown bits print as a
+    /// single such character.
+    pub fn to_radix_string(&self, radix: Radix) -> String {
+        let Some(digit_bits) = radix.digit_bits() else {
+            return match self.to_le_words() {
+                Ok(words) => decimal(words),
+                Err(_) => {
+                    let bits: Vec<LV> = (0..self.len()).map(|i| self.get(i)).collect();
+                    unknown_char(&bits).unwrap().to_string()
+                }
+            };
+        };
+        (0..self.len().div_ceil(digit_bits))
+            .rev()
+            .map(|digit| {
+                let start = digit * digit_bits;
+                let end = (start + digit_bits).min(self.len());
+                let bits: Vec<LV> = (start..end).map(|i| self.get(i)).collect();
+                unknown_char(&bits).unwrap_or_else(|| {
+                    let value = bits
+                        .iter()
+                        .enumerate()
+                        .map(|(i, lv)| u32::from(*lv == LV::H) << i)
+                        .sum();
+                    char::from_digit(value, 1 << digit_bits).unwrap()
+                })
+            })
+            .collect()
+    }
+
+    /// The bits as a sized literal like `8'hx5`, which `from_str` reads
+    /// back unless a digit is only partly unknown.
+    pub fn to_literal(&self, radix: Radix) -> String {
+        format!(
+            "{}'{}{}",
+            self.len(),
+            radix.letter(),
+            self.to_radix_string(radix)
+        )
+    }
+
+    /// Digits for the `fmt` traits, the whole literal with `#`.
+    fn fmt_radix(&self, f: &mut fmt::Formatter<'_>, radix: Radix) -> fmt::Result {
+        if f.alternate() {
+            f.pad(&self.to_literal(radix))
+        } else {
+            f.pad(&self.to_radix_string(radix))
+        }
+    }
+}
+
+impl fmt::Binary for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_radix(f, Radix::Binary)
+    }
+}
+
+impl fmt::Octal for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_radix(f, Radix::Octal)
+    }
+}
+
+impl fmt::LowerHex for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_radix(f, Radix::Hex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Bits {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("8'b10xz_0101").to_string(), "1010ZX01");
+        assert_eq!(parse("16'hAB_CD").to_u64(), Ok(0xabcd));
+        assert_eq!(parse("4'd9").to_u64(), Ok(9));
+        assert_eq!(parse("42").len(), 32);
+        assert_eq!(parse("-4'sd3").to_i64(), Ok(-3));
+        assert_eq!(parse("12'hx5").to_string(), "1010XXXXXXXX");
+        assert_eq!(parse("3'hx").to_string(), "XXX");
+        assert_eq!(parse("6'o7").to_u64(), Ok(7));
+        let big = parse("'d340282366920938463463374607431768211456");
+        assert_eq!(big.len(), 129);
+        assert_eq!(big.get(128), LV::H);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in [
+            "8'b102", "4'h1f", "0'b1", "8'q1", "'h", "-4'bx", "8'h_1", "abc",
+        ] {
+            assert!(bad.parse::<Bits>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let b = parse("8'hx5");
+        assert_eq!(b.to_literal(Radix::Hex), "8'hx5");
+        assert_eq!(format!("{:#x}", b), "8'hx5");
+        assert_eq!(format!("{:b}", b), "xxxx0101");
+        assert_eq!(b.to_radix_string(Radix::Decimal), "X");
+        assert_eq!(parse("8'b0z010101").to_radix_string(Radix::Hex), "Z5");
+        assert_eq!(format!("{:o}", parse("7'd64")), "100");
+        let big = parse("'d340282366920938463463374607431768211457");
+        assert_eq!(
+            big.to_radix_string(Radix::Decimal),
+            "340282366920938463463374607431768211457"
+        );
+        for (literal, radix) in [
+            ("12'hab5", Radix::Hex),
+            ("5'b1x0z1", Radix::Binary),
+            ("8'd200", Radix::Decimal),
+            ("9'o7x1", Radix::Octal),
+        ] {
+            assert_eq!(parse(literal).to_literal(radix), literal);
+        }
+    }
+}
@@ -0,0 +1,897 @@
+use std::{cmp::Ordering, fmt::Display, iter::zip};
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+mod convert;
+mod literal;
+mod ops;
+mod packed;
+mod slice;
+
+pub use literal::Radix;
+pub use packed::PackedBits;
+pub use slice::{IntoIter, Iter, RangeError};
+
+#[derive(Eq, PartialEq, Clone)]
+#[wasm_bindgen]
+pub enum LV {
+    H = 0,
+    L = 1,
+    X = 2,
+    Z = 3,
+}
+
+impl LV {
+    pub fn and(&self, other: &LV) -> LV {
+        match (&self, &other) {
+            (LV::L, _) => LV::L,
+            (_, LV::L) => LV::L,
+            (LV::Z, LV::H) => LV::X,
+            (&a, LV::H) => a.clone(),
+            (LV::H, LV::Z) => LV::X,
+            (LV::H, &b) => b.clone(),
+            (LV::X, LV::X) => LV::X,
+            (LV::X, LV::Z) => LV::X,
+            (LV::Z, LV::X) => LV::X,
+            (LV::Z, LV::Z) => LV::X,
+        }
+    }
+    pub fn or(&self, other: &LV) -> LV {
+        match (&self, &other) {
+            (LV::H, _) => LV::H,
+            (_, LV::H) => LV::H,
+            (LV::L, LV::Z) => LV::X,
+            (LV::L, &b) => b.clone(),
+            (LV::Z, LV::L) => LV::X,
+            (&a, LV::L) => a.clone(),
+            (LV::X, LV::X) => LV::X,
+            (LV::Z, LV::X) => LV::X,
+            (LV::X, LV::Z) => LV::X,
+            (LV::Z, LV::Z) => LV::X,
+        }
+    }
+    pub fn not(&self) -> LV {
+        match self {
+            LV::H => LV::L,
+            LV::L => LV::H,
+            LV::X => LV::X,
+            LV::Z => LV::X,
+        }
+    }
+    pub fn xor(&self, other: &LV) -> LV {
+        match (self, other) {
+            (LV::H, LV::H) | (LV::L, LV::L) => LV::L,
+            (LV::H, LV::L) | (LV::L, LV::H) => LV::H,
+            _ => LV::X,
+        }
+    }
+    pub fn xnor(&self, other: &LV) -> LV {
+        self.xor(other).not()
+    }
+    pub fn nand(&self, other: &LV) -> LV {
+        self.and(other).not()
+    }
+    pub fn nor(&self, other: &LV) -> LV {
+        self.or(other).not()
+    }
+    /// `self` implies `other`: 0 only for 1 and 0, 1 whenever `self` is 0
+    /// or `other` is 1.
+    pub fn implies(&self, other: &LV) -> LV {
+        self.not().or(other)
+    }
+
+    /// `a` when `sel` is 0, `b` when it is 1. An unknown `sel` still gives
+    /// the level both inputs agree on. Z passes as X, like through a gate.
+    pub fn mux(sel: &LV, a: &LV, b: &LV) -> LV {
+        match sel {
+            LV::L => a.and(a),
+            LV::H => b.and(b),
+            _ => a.and(a).resolve(&b.and(b)),
+        }
+    }
+
+    /// The level of a wire driven by both values: Z gives way to the other
+    /// driver, differing drivers fight and give X.
+    pub fn resolve(&self, other: &LV) -> LV {
+        match (self, other) {
+            (LV::Z, b) => b.clone(),
+            (a, LV::Z) => a.clone(),
+            (a, b) if a == b => a.clone(),
+            _ => LV::X,
+        }
+    }
+}
+
+impl LV {
+    /// Parses the character printed by `Display`, i.e. `1`, `0`, `X` or `Z`.
+    pub fn from_char(c: char) -> Option<LV> {
+        match c {
+            '1' => Some(LV::H),
+            '0' => Some(LV::L),
+            'x' | 'X' => Some(LV::X),
+            'z' | 'Z' => Some(LV::Z),
+            _ => None,
+        }
+    }
+}
+
+/// A single bit value that `Bits` can hold.
+pub trait LogicValue: Clone + PartialEq + Eq + std::fmt::Debug + Display {
+    /// How `Bits` of this value are stored.
+    type Store: BitStore<Self>;
+    /// The value of a wire nobody drives.
+    fn high_z() -> Self;
+    fn unknown() -> Self;
+    fn from_bool(value: bool) -> Self;
+    /// The level the value stands for, `None` if it is neither 0 nor 1.
+    fn to_bool(&self) -> Option<bool>;
+    fn and(&self, other: &Self) -> Self;
+    fn or(&self, other: &Self) -> Self;
+    fn not(&self) -> Self;
+    fn xor(&self, other: &Self) -> Self;
+    /// The value of a wire driven by both values.
+    fn resolve(&self, other: &Self) -> Self;
+    fn from_char(c: char) -> Option<Self>;
+}
+
+impl LogicValue for LV {
+    type Store = PackedBits;
+    fn high_z() -> Self {
+        LV::Z
+    }
+    fn unknown() -> Self {
+        LV::X
+    }
+    fn from_bool(value: bool) -> Self {
+        LV::from(value)
+    }
+    fn to_bool(&self) -> Option<bool> {
+        match self {
+            LV::H => Some(true),
+            LV::L => Some(false),
+            _ => None,
+        }
+    }
+    fn and(&self, other: &Self) -> Self {
+        LV::and(self, other)
+    }
+    fn or(&self, other: &Self) -> Self {
+        LV::or(self, other)
+    }
+    fn not(&self) -> Self {
+        LV::not(self)
+    }
+    fn xor(&self, other: &Self) -> Self {
+        LV::xor(self, other)
+    }
+    fn resolve(&self, other: &Self) -> Self {
+        LV::resolve(self, other)
+    }
+    fn from_char(c: char) -> Option<Self> {
+        LV::from_char(c)
+    }
+}
+
+impl Display for LV {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::fmt::Debug for LV {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::H => write!(f, "1"),
+            Self::L => write!(f, "0"),
+            Self::X => write!(f, "X"),
+            Self::Z => write!(f, "Z"),
+        }
+    }
+}
+
+/// The storage behind `Bits`. The provided operations go bit by bit,
+/// stores with a faster representation override them.
+pub trait BitStore<V: LogicValue>: Clone + PartialEq + Eq + FromIterator<V> {
+    fn filled(len: usize, value: V) -> Self {
+        (0..len).map(|_| value.clone()).collect()
+    }
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn get(&self, idx: usize) -> V;
+    fn set(&mut self, idx: usize, value: V);
+    fn and(&self, other: &Self) -> Self {
+        (0..self.len())
+            .map(|i| self.get(i).and(&other.get(i)))
+            .collect()
+    }
+    fn or(&self, other: &Self) -> Self {
+        (0..self.len())
+            .map(|i| self.get(i).or(&other.get(i)))
+            .collect()
+    }
+    fn not(&self) -> Self {
+        (0..self.len()).map(|i| self.get(i).not()).collect()
+    }
+    fn xor(&self, other: &Self) -> Self {
+        (0..self.len())
+            .map(|i| self.get(i).xor(&other.get(i)))
+            .collect()
+    }
+    fn resolve(&self, other: &Self) -> Self {
+        (0..self.len())
+            .map(|i| self.get(i).resolve(&other.get(i)))
+            .collect()
+    }
+}
+
+impl<V: LogicValue> BitStore<V> for Vec<V> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn get(&self, idx: usize) -> V {
+        self[idx].clone()
+    }
+    fn set(&mut self, idx: usize, value: V) {
+        self[idx] = value;
+    }
+}
+
+/// A vector of logic values, index 0 being the least significant bit.
+/// Simulation uses `LV`, other `LogicValue`s such as `StdLogic` work for
+/// the bitwise operations.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Bits<V: LogicValue = LV> {
+    value: V::Store,
+}
+
+impl<V: LogicValue> Display for Bits<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for i in 0..self.len() {
+            write!(f, "{}", self.get(i))?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: LogicValue> std::fmt::Debug for Bits<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bits({})", self)
+    }
+}
+
+impl<V: LogicValue> FromIterator<V> for Bits<V> {
+    fn from_iter<T: IntoIterator<Item = V>>(iter: T) -> Self {
+        Bits {
+            value: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<V: LogicValue> Bits<V> {
+    pub fn new(n: usize) -> Self {
+        Bits {
+            value: V::Store::filled(n, V::high_z()),
+        }
+    }
+
+    pub fn set(&mut self, idx: usize, value: V) {
+        self.value.set(idx, value);
+    }
+
+    pub fn get(&self, idx: usize) -> V {
+        self.value.get(idx)
+    }
+
+    pub fn len(&self) -> usize {
+        self.value.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn and(&self, other: &Self) -> Self {
+        self.check_same_len(other);
+        Bits {
+            value: self.value.and(&other.value),
+        }
+    }
+    pub fn or(&self, other: &Self) -> Self {
+        self.check_same_len(other);
+        Bits {
+            value: self.value.or(&other.value),
+        }
+    }
+    /// Bitwise `LogicValue::resolve` of two drivers of the same wires.
+    pub fn resolve(&self, other: &Self) -> Self {
+        self.check_same_len(other);
+        Bits {
+            value: self.value.resolve(&other.value),
+        }
+    }
+
+    pub fn not(&self) -> Self {
+        Bits {
+            value: self.value.not(),
+        }
+    }
+    pub fn xor(&self, other: &Self) -> Self {
+        self.check_same_len(other);
+        Bits {
+            value: self.value.xor(&other.value),
+        }
+    }
+    pub fn xnor(&self, other: &Self) -> Self {
+        self.xor(other).not()
+    }
+    pub fn nand(&self, other: &Self) -> Self {
+        self.and(other).not()
+    }
+    pub fn nor(&self, other: &Self) -> Self {
+        self.or(other).not()
+    }
+    /// Bitwise `self` implies `other`.
+    pub fn implies(&self, other: &Self) -> Self {
+        self.not().or(other)
+    }
+
+    fn check_same_len(&self, other: &Self) {
+        if self.len() != other.len() {
+            panic!("Can't compare bits of different sizes");
+        }
+    }
+
+    /// Converts every bit, e.g. between `LV` and `StdLogic`.
+    pub fn map<W: LogicValue>(&self, f: impl Fn(&V) -> W) -> Bits<W> {
+        self.iter().map(|v| f(&v)).collect()
+    }
+}
+
+impl Bits {
+    /// Sets the bits to `value` in two's complement, cutting off the high
+    /// bits or repeating the sign to fill the width. `from_i64` fails
+    /// instead when the value doesn't fit.
+    pub fn set_num(self, value: i64) -> Bits {
+        let sign = if value < 0 { u64::MAX } else { 0 };
+        let words: Vec<u64> = std::iter::once(value as u64)
+            .chain(std::iter::repeat(sign))
+            .take(self.len().div_ceil(64))
+            .collect();
+        Bits::from_le_words(self.len(), &words)
+    }
+
+    /// Bitwise `LV::mux`, `sel` choosing for all bits at once.
+    pub fn mux(sel: &LV, a: &Bits, b: &Bits) -> Bits {
+        a.check_same_len(b);
+        match sel {
+            LV::L => a.and(a),
+            LV::H => b.and(b),
+            _ => a.and(a).resolve(&b.and(b)),
+        }
+    }
+
+    /// The bits as booleans if none of them is X or Z.
+    fn to_bools(&self) -> Option<Vec<bool>> {
+        (0..self.len())
+            .map(|i| match self.get(i) {
+                LV::H => Some(true),
+                LV::L => Some(false),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn from_bools(bools: &[bool]) -> Bits {
+        bools
+            .iter()
+            .map(|b| if *b { LV::H } else { LV::L })
+            .collect()
+    }
+
+    fn unknown(n: usize) -> Bits {
+        (0..n).map(|_| LV::X).collect()
+    }
+
+    /// Adds `other` and the carry, returning the sum and the carry out. Any
+    /// unknown operand bit makes the whole result X.
+    pub fn add(&self, other: &Bits, carry: &LV) -> (Bits, LV) {
+        self.check_same_len(other);
+        let carry = match carry {
+            LV::H => true,
+            LV::L => false,
+            _ => return (Bits::unknown(self.len()), LV::X),
+        };
+        match (self.to_bools(), other.to_bools()) {
+            (Some(a), Some(b)) => {
+                let (sum, carry) = add_bools(&a, &b, carry);
+                (Bits::from_bools(&sum), LV::from(carry))
+            }
+            _ => (Bits::unknown(self.len()), LV::X),
+        }
+    }
+
+    /// Subtracts `other` and the borrow, returning the difference and the
+    /// borrow out, which is set when the unsigned result wrapped around.
+    pub fn sub(&self, other: &Bits, borrow: &LV) -> (Bits, LV) {
+        let (diff, carry) = self.add(&other.not(), &borrow.not());
+        (diff, carry.not())
+    }
+
+    /// Two's complement negation.
+    pub fn negate(&self) -> Bits {
+        Bits::new(self.len()).set_num(0).sub(self, &LV::L).0
+    }
+
+    /// Unsigned product with as many bits as both operands together.
+    pub fn mul(&self, other: &Bits) -> Bits {
+        let width = self.len() + other.len();
+        match (self.to_bools(), other.to_bools()) {
+            (Some(a), Some(b)) => {
+                let mut product = vec![false; width];
+                for (shift, bit) in b.iter().enumerate() {
+                    if *bit {
+                        let mut addend = vec![false; width];
+                        addend[shift..shift + a.len()].copy_from_slice(&a);
+                        product = add_bools(&product, &addend, false).0;
+                    }
+                }
+                Bits::from_bools(&product)
+            }
+            _ => Bits::unknown(width),
+        }
+    }
+
+    /// Unsigned quotient and remainder. Both are X when dividing by zero.
+    pub fn div_rem(&self, other: &Bits) -> (Bits, Bits) {
+        self.check_same_len(other);
+        let n = self.len();
+        match (self.to_bools(), other.to_bools()) {
+            (Some(a), Some(b)) if b.contains(&true) => {
+                let divisor = Bits::from_bools(&b);
+                let mut quotient = vec![false; n];
+                let mut remainder = Bits::new(n).set_num(0);
+                for i in (0..n).rev() {
+                    // remainder = remainder << 1 | a[i], a shifted out bit
+                    // means it is larger than any divisor
+                    let overflow = remainder.get(n - 1) == LV::H;
+                    remainder = std::iter::once(LV::from(a[i]))
+                        .chain((0..n - 1).map(|j| remainder.get(j)))
+                        .collect();
+                    let (diff, borrow) = remainder.sub(&divisor, &LV::L);
+                    if overflow || borrow == LV::L {
+                        remainder = diff;
+                        quotient[i] = true;
+                    }
+                }
+                (Bits::from_bools(&quotient), remainder)
+            }
+            _ => (Bits::unknown(n), Bits::unknown(n)),
+        }
+    }
+
+    /// Bitwise equality: L as soon as a known bit differs, X if the known
+    /// bits match but some are unknown.
+    pub fn eq_lv(&self, other: &Bits) -> LV {
+        self.check_same_len(other);
+        let mut result = LV::H;
+        for i in 0..self.len() {
+            match (self.get(i), other.get(i)) {
+                (LV::H, LV::L) | (LV::L, LV::H) => return LV::L,
+                (LV::H, LV::H) | (LV::L, LV::L) => {}
+                _ => result = LV::X,
+            }
+        }
+        result
+    }
+
+    /// Compares as unsigned numbers, `None` if any bit is unknown.
+    pub fn cmp_unsigned(&self, other: &Bits) -> Option<Ordering> {
+        self.check_same_len(other);
+        let (a, b) = (self.to_bools()?, other.to_bools()?);
+        Some(a.iter().rev().cmp(b.iter().rev()))
+    }
+
+    /// Compares as two's complement numbers, `None` if any bit is unknown.
+    pub fn cmp_signed(&self, other: &Bits) -> Option<Ordering> {
+        let ordering = self.cmp_unsigned(other)?;
+        match self.len().checked_sub(1) {
+            // Differing sign bits flip the unsigned order
+            Some(msb) if self.get(msb) != other.get(msb) => Some(ordering.reverse()),
+            _ => Some(ordering),
+        }
+    }
+
+    /// Shifts or rotates by a fixed number of positions. Shifting by the
+    /// width or more leaves only fill bits.
+    pub fn shift(&self, kind: Shift, amount: usize) -> Bits {
+        let n = self.len();
+        let msb = n.checked_sub(1).map(|i| self.get(i));
+        (0..n)
+            .map(|i| match kind {
+                Shift::Left if i >= amount => self.get(i - amount),
+                Shift::Left => LV::L,
+                Shift::Right | Shift::ArithmeticRight if amount < n - i => self.get(i + amount),
+                Shift::Right => LV::L,
+                Shift::ArithmeticRight => msb.clone().unwrap(),
+                Shift::RotateLeft => self.get((i + n - amount % n) % n),
+                Shift::RotateRight => self.get((i + amount % n) % n),
+            })
+            .collect()
+    }
+
+    /// Shifts or rotates by an unsigned amount. Unknown bits of the amount
+    /// stand for both 0 and 1, result bits stay known where all of the
+    /// possible results agree.
+    pub fn shift_by(&self, kind: Shift, amount: &Bits) -> Bits {
+        let n = self.len();
+        let rotate = matches!(kind, Shift::RotateLeft | Shift::RotateRight);
+        let modulus = n.max(1);
+        // Possible effective amounts, shifts saturate at n and rotations wrap
+        let mut possible = vec![false; n + 1];
+        possible[0] = true;
+        // The effective amount of bit i, kept reduced so it never overflows
+        let mut step = if rotate { 1 % modulus } else { 1.min(n) };
+        for i in 0..amount.len() {
+            if i > 0 {
+                step = if rotate {
+                    step * 2 % modulus
+                } else {
+                    (step * 2).min(n)
+                };
+            }
+            let lv = amount.get(i);
+            if lv == LV::L {
+                continue;
+            }
+            let mut next = if lv == LV::H {
+                vec![false; n + 1]
+            } else {
+                possible.clone()
+            };
+            for a in (0..=n).filter(|a| possible[*a]) {
+                let moved = if rotate {
+                    (a + step) % modulus
+                } else {
+                    (a + step).min(n)
+                };
+                next[moved] = true;
+            }
+            possible = next;
+        }
+        let mut results = (0..=n)
+            .filter(|a| possible[*a])
+            .map(|a| self.shift(kind, a));
+        let first = results.next().unwrap();
+        results.fold(first, |acc, other| {
+            (0..n)
+                .map(|i| match (acc.get(i), other.get(i)) {
+                    (a, b) if a == b => a,
+                    _ => LV::X,
+                })
+                .collect()
+        })
+    }
+}
+
+/// How hard a driver pushes its levels onto a lump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Strength {
+    /// Drives nothing, like Z on every bit.
+    HighZ,
+    /// Pull resistors and bus keepers.
+    Weak,
+    /// Gate outputs.
+    Strong,
+    /// Power rails, overriding everything else.
+    Supply,
+}
+
+/// Combines the drivers of a lump bit by bit: the strongest drivers that
+/// don't drive Z decide, where equally strong ones disagree the bit is X.
+/// Returns the levels and the indices of bits where two equally strong
+/// drivers drive opposite known levels.
+pub fn resolve_drivers<'a>(
+    width: usize,
+    drivers: impl Iterator<Item = (&'a Bits, Strength)> + Clone,
+) -> (Bits, Vec<usize>) {
+    let mut conflicts = Vec::new();
+    let levels = (0..width)
+        .map(|i| {
+            // The strongest strength seen and which levels it drives
+            let mut best: Option<(Strength, bool, bool, bool)> = None;
+            for (bits, strength) in drivers.clone() {
+                let lv = bits.get(i);
+                if lv == LV::Z || strength == Strength::HighZ {
+                    continue;
+                }
+                let (s, high, low, unknown) = match best {
+                    Some(current) if current.0 > strength => continue,
+                    Some(current) if current.0 == strength => current,
+                    _ => (strength, false, false, false),
+                };
+                best = Some((
+                    s,
+                    high || lv == LV::H,
+                    low || lv == LV::L,
+                    unknown || lv == LV::X,
+                ));
+            }
+            match best {
+                None => LV::Z,
+                Some((_, true, true, _)) => {
+                    conflicts.push(i);
+                    LV::X
+                }
+                Some((_, true, false, false)) => LV::H,
+                Some((_, false, true, false)) => LV::L,
+                Some(_) => LV::X,
+            }
+        })
+        .collect();
+    (levels, conflicts)
+}
+
+/// Direction and fill of `Bits::shift`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shift {
+    /// Towards the most significant bit, filling with 0.
+    Left,
+    /// Towards the least significant bit, filling with 0.
+    Right,
+    /// Towards the least significant bit, repeating the sign bit.
+    ArithmeticRight,
+    RotateLeft,
+    RotateRight,
+}
+
+/// Ripple carry addition of two equally long little endian bit vectors.
+fn add_bools(a: &[bool], b: &[bool], mut carry: bool) -> (Vec<bool>, bool) {
+    let sum = zip(a, b)
+        .map(|(a, b)| {
+            let s = a ^ b ^ carry;
+            carry = (a & b) | (carry & (a ^ b));
+            s
+        })
+        .collect();
+    (sum, carry)
+}
+
+impl From<bool> for LV {
+    fn from(value: bool) -> Self {
+        if value {
+            LV::H
+        } else {
+            LV::L
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lv_and() {
+        assert!(LV::H.and(&LV::L) == LV::L);
+        assert!(LV::L.and(&LV::H) == LV::L);
+        assert!(LV::L.and(&LV::X) == LV::L);
+        assert!(LV::H.and(&LV::H) == LV::H);
+        assert!(LV::H.and(&LV::Z) == LV::X);
+        assert!(LV::H.and(&LV::X) == LV::X);
+    }
+
+    #[test]
+    fn test_lv_or() {
+        assert!(LV::L.or(&LV::L) == LV::L);
+        assert!(LV::H.or(&LV::L) == LV::H);
+        assert!(LV::L.or(&LV::H) == LV::H);
+        assert!(LV::Z.or(&LV::H) == LV::H);
+        assert!(LV::Z.or(&LV::L) == LV::X);
+        assert!(LV::X.or(&LV::L) == LV::X);
+    }
+
+    #[test]
+    fn test_lv_not() {
+        assert!(LV::H.not() == LV::L);
+        assert!(LV::L.not() == LV::H);
+        assert!(LV::Z.not() == LV::X);
+        assert!(LV::X.not() == LV::X);
+    }
+
+    #[test]
+    fn test_lv_xor_family() {
+        let all = [LV::H, LV::L, LV::X, LV::Z];
+        let table = |op: fn(&LV, &LV) -> LV| -> String {
+            all.iter()
+                .flat_map(|a| all.iter().map(move |b| op(a, b).to_string()))
+                .collect()
+        };
+        // Rows for a = 1, 0, X, Z against b = 1, 0, X, Z
+        assert_eq!(table(LV::xor), "01XX10XXXXXXXXXX");
+        assert_eq!(table(LV::xnor), "10XX01XXXXXXXXXX");
+        assert_eq!(table(LV::nand), "01XX1111X1XXX1XX");
+        assert_eq!(table(LV::nor), "000001XX0XXX0XXX");
+        assert_eq!(table(LV::implies), "10XX11111XXX1XXX");
+    }
+
+    #[test]
+    fn test_lv_mux() {
+        assert_eq!(LV::mux(&LV::L, &LV::H, &LV::L), LV::H);
+        assert_eq!(LV::mux(&LV::H, &LV::H, &LV::L), LV::L);
+        assert_eq!(LV::mux(&LV::X, &LV::H, &LV::H), LV::H);
+        assert_eq!(LV::mux(&LV::Z, &LV::H, &LV::L), LV::X);
+        assert_eq!(LV::mux(&LV::H, &LV::H, &LV::Z), LV::X);
+        assert_eq!(LV::mux(&LV::X, &LV::Z, &LV::Z), LV::X);
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert!(LV::Z.resolve(&LV::H) == LV::H);
+        assert!(LV::L.resolve(&LV::Z) == LV::L);
+        assert!(LV::L.resolve(&LV::L) == LV::L);
+        assert!(LV::L.resolve(&LV::H) == LV::X);
+        assert!(LV::Z.resolve(&LV::Z) == LV::Z);
+        assert!(LV::X.resolve(&LV::Z) == LV::X);
+    }
+
+    #[test]
+    fn test_resolve_drivers() {
+        let pull_up = num(2, 0b11);
+        let low = num(2, 0b00);
+        let mut half = num(2, 0b01);
+        half.set(1, LV::Z);
+        let drivers = [
+            (&pull_up, Strength::Weak),
+            (&half, Strength::Strong),
+            (&low, Strength::HighZ),
+        ];
+        let (levels, conflicts) = resolve_drivers(2, drivers.iter().cloned());
+        assert_eq!(levels, num(2, 0b11));
+        assert!(conflicts.is_empty());
+
+        let drivers = [(&half, Strength::Strong), (&low, Strength::Strong)];
+        let (levels, conflicts) = resolve_drivers(2, drivers.iter().cloned());
+        assert_eq!(levels.to_string(), "X0");
+        assert_eq!(conflicts, [0]);
+
+        let drivers = [(&low, Strength::Supply), (&pull_up, Strength::Strong)];
+        assert_eq!(resolve_drivers(2, drivers.iter().cloned()).0, low);
+        assert_eq!(resolve_drivers(2, [].iter().cloned()).0, Bits::new(2));
+
+        // An X in between doesn't hide the conflict, whatever the order
+        let levels: Vec<Bits> = [LV::H, LV::X, LV::L]
+            .map(|lv| std::iter::once(lv).collect())
+            .to_vec();
+        for order in [[0, 1, 2], [2, 0, 1], [1, 2, 0], [2, 1, 0]] {
+            let drivers = order.map(|i| (&levels[i], Strength::Strong));
+            let (value, conflicts) = resolve_drivers(1, drivers.iter().cloned());
+            assert_eq!((value.to_string(), conflicts), ("X".to_string(), vec![0]));
+        }
+        let drivers = [
+            (&levels[1], Strength::Strong),
+            (&levels[0], Strength::Strong),
+        ];
+        assert!(resolve_drivers(1, drivers.iter().cloned()).1.is_empty());
+        let drivers = [
+            (&levels[0], Strength::Weak),
+            (&levels[2], Strength::Weak),
+            (&levels[1], Strength::Strong),
+        ];
+        assert!(resolve_drivers(1, drivers.iter().cloned()).1.is_empty());
+    }
+
+    #[test]
+    fn test_bits_subrange() {
+        let tmp: Bits = Bits::new(8);
+        assert_eq!(tmp.get_range(0, 4).unwrap().len(), 4);
+        assert_eq!(tmp.get_range(4, 8).unwrap().len(), 4);
+        assert_eq!(tmp.get_range(8, 8).unwrap().len(), 0);
+        assert!(tmp.get_range(4, 9).is_err());
+        assert!(tmp.get_range(5, 4).is_err());
+    }
+
+    fn num(width: usize, value: i64) -> Bits {
+        Bits::new(width).set_num(value)
+    }
+
+    #[test]
+    fn test_add_sub() {
+        assert_eq!(num(4, 9).add(&num(4, 5), &LV::L), (num(4, 14), LV::L));
+        assert_eq!(num(4, 9).add(&num(4, 7), &LV::H), (num(4, 1), LV::H));
+        assert_eq!(num(4, 3).sub(&num(4, 5), &LV::L), (num(4, -2), LV::H));
+        assert_eq!(num(4, 5).sub(&num(4, 3), &LV::H), (num(4, 1), LV::L));
+        assert_eq!(num(4, 5).negate(), num(4, -5));
+        let (sum, carry) = num(4, 1).add(&Bits::new(4), &LV::L);
+        assert_eq!(sum.to_string(), "XXXX");
+        assert_eq!(carry, LV::X);
+    }
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(num(4, 13).mul(&num(4, 11)), num(8, 143));
+        assert_eq!(num(8, 200).div_rem(&num(8, 7)), (num(8, 28), num(8, 4)));
+        assert_eq!(num(4, 15).div_rem(&num(4, 9)), (num(4, 1), num(4, 6)));
+        let (q, r) = num(4, 3).div_rem(&num(4, 0));
+        assert_eq!(
+            (q.to_string(), r.to_string()),
+            ("XXXX".into(), "XXXX".into())
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(num(4, 3).cmp_unsigned(&num(4, -1)), Some(Ordering::Less));
+        assert_eq!(num(4, 3).cmp_signed(&num(4, -1)), Some(Ordering::Greater));
+        assert_eq!(num(4, -3).cmp_signed(&num(4, -1)), Some(Ordering::Less));
+        assert_eq!(num(4, 3).cmp_signed(&Bits::new(4)), None);
+        let mut partial = num(4, 3);
+        partial.set(3, LV::X);
+        assert_eq!(partial.eq_lv(&num(4, 3)), LV::X);
+        assert_eq!(partial.eq_lv(&num(4, 2)), LV::L);
+        assert_eq!(num(4, 3).eq_lv(&num(4, 3)), LV::H);
+    }
+
+    #[test]
+    fn test_shift() {
+        let b = num(4, 0b1011);
+        assert_eq!(b.shift(Shift::Left, 1), num(4, 0b0110));
+        assert_eq!(b.shift(Shift::Right, 2), num(4, 0b0010));
+        assert_eq!(b.shift(Shift::ArithmeticRight, 2), num(4, 0b1110));
+        assert_eq!(b.shift(Shift::RotateLeft, 1), num(4, 0b0111));
+        assert_eq!(b.shift(Shift::RotateRight, 5), num(4, 0b1101));
+        assert_eq!(b.shift(Shift::Left, 9), num(4, 0));
+        assert_eq!(b.shift(Shift::Left, usize::MAX), num(4, 0));
+        assert_eq!(b.shift(Shift::Right, usize::MAX), num(4, 0));
+        assert_eq!(b.shift(Shift::ArithmeticRight, usize::MAX), num(4, 0b1111));
+        // usize::MAX is 3 mod 4
+        assert_eq!(b.shift(Shift::RotateRight, usize::MAX), num(4, 0b0111));
+        assert_eq!(b.shift(Shift::RotateLeft, usize::MAX), num(4, 0b1101));
+        assert_eq!(b.shift_by(Shift::Right, &num(8, 200)), num(4, 0));
+        assert_eq!(b.shift_by(Shift::RotateLeft, &num(3, 5)), num(4, 0b0111));
+    }
+
+    #[test]
+    fn test_shift_by_unknown() {
+        let mut amount = num(2, 0);
+        amount.set(0, LV::X);
+        // 1011 or 0101 from shifting right by 0 or 1, printed least
+        // significant bit first
+        assert_eq!(
+            num(4, 0b1011).shift_by(Shift::Right, &amount).to_string(),
+            "1XXX"
+        );
+        // 1111 or 1111 from rotating by 0 or 1
+        assert_eq!(
+            num(4, 0b1111).shift_by(Shift::RotateRight, &amount),
+            num(4, 15)
+        );
+        // 0011 or 0110
+        assert_eq!(
+            num(4, 0b0011).shift_by(Shift::Left, &amount).to_string(),
+            "X1X0"
+        );
+        // Amount bits past 64 still count, 2^64 and 2^65 are 1 and 2 mod 3
+        let mut amount = num(70, 0);
+        amount.set(64, LV::H);
+        assert_eq!(
+            num(3, 0b001).shift_by(Shift::RotateLeft, &amount),
+            num(3, 0b010)
+        );
+        amount.set(65, LV::H);
+        assert_eq!(
+            num(3, 0b001).shift_by(Shift::RotateLeft, &amount),
+            num(3, 0b001)
+        );
+        assert_eq!(num(3, 0b001).shift_by(Shift::Left, &amount), num(3, 0));
+    }
+
+    #[test]
+    fn test_slice_concat() {
+        let b = num(8, 0b1010_0110);
+        assert_eq!(b.slice(4, 8), num(4, 0b1010));
+        assert_eq!(b.slice(0, 4).concat(&b.slice(4, 8)), b);
+        assert_eq!(b.slice(3, 3).len(), 0);
+    }
+}
@@ -0,0 +1,104 @@
+//! `&`, `|`, `^` and `!` on `LV` and `Bits`, by value and by reference.
+//! Bits of different widths panic like the named methods.
+
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+use super::{Bits, LogicValue, LV};
+
+macro_rules! binary_ops {
+    ($($op:ident $method:ident => $name:ident),*) => {$(
+        impl $op for LV {
+            type Output = LV;
+            fn $method(self, rhs: LV) -> LV {
+                LV::$name(&self, &rhs)
+            }
+        }
+
+        impl $op for &LV {
+            type Output = LV;
+            fn $method(self, rhs: &LV) -> LV {
+                LV::$name(self, rhs)
+            }
+        }
+
+        impl<V: LogicValue> $op for Bits<V> {
+            type Output = Bits<V>;
+            fn $method(self, rhs: Bits<V>) -> Bits<V> {
+                Bits::$name(&self, &rhs)
+            }
+        }
+
+        impl<V: LogicValue> $op for &Bits<V> {
+            type Output = Bits<V>;
+            fn $method(self, rhs: &Bits<V>) -> Bits<V> {
+                Bits::$name(self, rhs)
+            }
+        }
+    )*};
+}
+
+binary_ops!(BitAnd bitand => and, BitOr bitor => or, BitXor bitxor => xor);
+
+impl Not for LV {
+    type Output = LV;
+    fn not(self) -> LV {
+        LV::not(&self)
+    }
+}
+
+impl Not for &LV {
+    type Output = LV;
+    fn not(self) -> LV {
+        LV::not(self)
+    }
+}
+
+impl<V: LogicValue> Not for Bits<V> {
+    type Output = Bits<V>;
+    fn not(self) -> Bits<V> {
+        Bits::not(&self)
+    }
+}
+
+impl<V: LogicValue> Not for &Bits<V> {
+    type Output = Bits<V>;
+    fn not(self) -> Bits<V> {
+        Bits::not(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Bits {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_operators() {
+        // A half adder
+        let (a, b) = (parse("4'b1100"), parse("4'b1010"));
+        assert_eq!(&a ^ &b, parse("4'b0110"));
+        assert_eq!(&a & &b, parse("4'b1000"));
+        assert_eq!(a.clone() | b.clone(), parse("4'b1110"));
+        assert_eq!(!a, parse("4'b0011"));
+        assert_eq!(LV::H ^ LV::Z, LV::X);
+        assert_eq!(!&LV::L & LV::H, LV::H);
+
+        let x = parse("4'bx01z");
+        assert_eq!((&x ^ &b).to_string(), "X00X");
+        assert_eq!(x.xnor(&parse("4'b0000")).to_string(), "X01X");
+        assert_eq!(x.nand(&parse("4'b0110")).to_string(), "1011");
+        assert_eq!(x.nor(&parse("4'b0000")).to_string(), "X01X");
+        assert_eq!(x.implies(&parse("4'b0000")).to_string(), "X01X");
+    }
+
+    #[test]
+    fn test_bits_mux() {
+        let (a, b) = (parse("4'b1100"), parse("4'b1z10"));
+        assert_eq!(Bits::mux(&LV::L, &a, &b), a);
+        assert_eq!(Bits::mux(&LV::H, &a, &b).to_string(), "01X1");
+        assert_eq!(Bits::mux(&LV::X, &a, &b).to_string(), "0XX1");
+    }
+}
@@ -0,0 +1,311 @@
+//! Storage of `LV` bits in two planes of 64 bit words, so the logic
+//! operations handle 64 bits at once. Up to `INLINE_WORDS` words are kept
+//! inline, which covers the usual bus widths without allocating.
+
+use super::{BitStore, LV};
+
+const WORD_BITS: usize = 64;
+const INLINE_WORDS: usize = 2;
+
+/// 64 bits: L is 0 in both planes, H sets `value`, Z sets `unknown` and X
+/// sets both.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+struct Word {
+    value: u64,
+    unknown: u64,
+}
+
+impl Word {
+    fn high(self) -> u64 {
+        self.value & !self.unknown
+    }
+
+    fn low(self) -> u64 {
+        !self.value & !self.unknown
+    }
+
+    /// Bits that are neither high nor low become X.
+    fn from_levels(high: u64, low: u64) -> Word {
+        let unknown = !(high | low);
+        Word {
+            value: high | unknown,
+            unknown,
+        }
+    }
+
+    fn and(self, other: Word) -> Word {
+        Word::from_levels(self.high() & other.high(), self.low() | other.low())
+    }
+
+    fn or(self, other: Word) -> Word {
+        Word::from_levels(self.high() | other.high(), self.low() & other.low())
+    }
+
+    fn not(self) -> Word {
+        Word::from_levels(self.low(), self.high())
+    }
+
+    fn xor(self, other: Word) -> Word {
+        Word::from_levels(
+            (self.high() & other.low()) | (self.low() & other.high()),
+            (self.high() & other.high()) | (self.low() & other.low()),
+        )
+    }
+
+    fn resolve(self, other: Word) -> Word {
+        let floating = |w: Word| w.unknown & !w.value;
+        let same = !((self.value ^ other.value) | (self.unknown ^ other.unknown));
+        let take_self = floating(other) | same;
+        let take_other = floating(self) & !take_self;
+        let fight = !(take_self | take_other);
+        Word {
+            value: (take_self & self.value) | (take_other & other.value) | fight,
+            unknown: (take_self & self.unknown) | (take_other & other.unknown) | fight,
+        }
+    }
+
+    fn filled(lv: &LV) -> Word {
+        let plane = |set: bool| if set { u64::MAX } else { 0 };
+        Word {
+            value: plane(matches!(lv, LV::H | LV::X)),
+            unknown: plane(matches!(lv, LV::X | LV::Z)),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Words {
+    Inline([Word; INLINE_WORDS]),
+    Heap(Vec<Word>),
+}
+
+/// The `BitStore` of `Bits<LV>`. Bits past `len` are kept L, so equal
+/// values have equal words.
+#[derive(Clone)]
+pub struct PackedBits {
+    len: usize,
+    words: Words,
+}
+
+impl PackedBits {
+    fn zeroed(len: usize) -> PackedBits {
+        let count = len.div_ceil(WORD_BITS);
+        let words = if count <= INLINE_WORDS {
+            Words::Inline([Word::default(); INLINE_WORDS])
+        } else {
+            Words::Heap(vec![Word::default(); count])
+        };
+        PackedBits { len, words }
+    }
+
+    fn words(&self) -> &[Word] {
+        let count = self.len.div_ceil(WORD_BITS);
+        match &self.words {
+            Words::Inline(words) => &words[..count],
+            Words::Heap(words) => words,
+        }
+    }
+
+    fn words_mut(&mut self) -> &mut [Word] {
+        let count = self.len.div_ceil(WORD_BITS);
+        match &mut self.words {
+            Words::Inline(words) => &mut words[..count],
+            Words::Heap(words) => words,
+        }
+    }
+
+    fn clear_unused(&mut self) {
+        let used = self.len % WORD_BITS;
+        if let (Some(last), true) = (self.words_mut().last_mut(), used != 0) {
+            let mask = (1 << used) - 1;
+            last.value &= mask;
+            last.unknown &= mask;
+        }
+    }
+
+    fn map_words(&self, f: impl Fn(Word) -> Word) -> PackedBits {
+        let mut result = self.clone();
+        for word in result.words_mut() {
+            *word = f(*word);
+        }
+        result.clear_unused();
+        result
+    }
+
+    fn zip_words(&self, other: &PackedBits, f: impl Fn(Word, Word) -> Word) -> PackedBits {
+        let mut result = self.clone();
+        for (word, other) in result.words_mut().iter_mut().zip(other.words()) {
+            *word = f(*word, *other);
+        }
+        result.clear_unused();
+        result
+    }
+
+    /// Known bits from little endian words, words past `len` are ignored
+    /// and missing ones are 0.
+    pub(super) fn from_levels(len: usize, levels: &[u64]) -> PackedBits {
+        let mut bits = PackedBits::zeroed(len);
+        for (word, level) in bits.words_mut().iter_mut().zip(levels) {
+            word.value = *level;
+        }
+        bits.clear_unused();
+        bits
+    }
+
+    /// The levels as little endian words, or the index of the first bit
+    /// that is X or Z.
+    pub(super) fn levels(&self) -> Result<Vec<u64>, usize> {
+        for (i, word) in self.words().iter().enumerate() {
+            if word.unknown != 0 {
+                return Err(i * WORD_BITS + word.unknown.trailing_zeros() as usize);
+            }
+        }
+        Ok(self.words().iter().map(|word| word.value).collect())
+    }
+
+    fn push(&mut self, lv: &LV) {
+        if self.len.is_multiple_of(WORD_BITS) {
+            let count = self.len / WORD_BITS;
+            match &mut self.words {
+                Words::Inline(words) if count == INLINE_WORDS => {
+                    let mut heap = words.to_vec();
+                    heap.push(Word::default());
+                    self.words = Words::Heap(heap);
+                }
+                Words::Inline(_) => {}
+                Words::Heap(words) => words.push(Word::default()),
+            }
+        }
+        self.len += 1;
+        self.set(self.len - 1, lv.clone());
+    }
+}
+
+impl PartialEq for PackedBits {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.words() == other.words()
+    }
+}
+
+impl Eq for PackedBits {}
+
+impl std::fmt::Debug for PackedBits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for i in 0..self.len {
+            write!(f, "{}", self.get(i))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<LV> for PackedBits {
+    fn from_iter<T: IntoIterator<Item = LV>>(iter: T) -> Self {
+        let iter = iter.into_iter();
+        let mut bits = PackedBits::zeroed(0);
+        if let (_, Some(upper)) = iter.size_hint() {
+            if upper > INLINE_WORDS * WORD_BITS {
+                bits.words = Words::Heap(Vec::with_capacity(upper.div_ceil(WORD_BITS)));
+            }
+        }
+        for lv in iter {
+            bits.push(&lv);
+        }
+        bits
+    }
+}
+
+impl BitStore<LV> for PackedBits {
+    fn filled(len: usize, value: LV) -> Self {
+        let mut bits = PackedBits::zeroed(len);
+        let word = Word::filled(&value);
+        bits.words_mut().fill(word);
+        bits.clear_unused();
+        bits
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, idx: usize) -> LV {
+        assert!(idx < self.len, "Bit {} out of range {}", idx, self.len);
+        let word = self.words()[idx / WORD_BITS];
+        let bit = 1 << (idx % WORD_BITS);
+        match (word.value & bit != 0, word.unknown & bit != 0) {
+            (true, false) => LV::H,
+            (false, false) => LV::L,
+            (true, true) => LV::X,
+            (false, true) => LV::Z,
+        }
+    }
+
+    fn set(&mut self, idx: usize, value: LV) {
+        assert!(idx < self.len, "Bit {} out of range {}", idx, self.len);
+        let filled = Word::filled(&value);
+        let word = &mut self.words_mut()[idx / WORD_BITS];
+        let bit = 1 << (idx % WORD_BITS);
+        word.value = (word.value & !bit) | (filled.value & bit);
+        word.unknown = (word.unknown & !bit) | (filled.unknown & bit);
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        self.zip_words(other, Word::and)
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        self.zip_words(other, Word::or)
+    }
+
+    fn not(&self) -> Self {
+        self.map_words(Word::not)
+    }
+
+    fn xor(&self, other: &Self) -> Self {
+        self.zip_words(other, Word::xor)
+    }
+
+    fn resolve(&self, other: &Self) -> Self {
+        self.zip_words(other, Word::resolve)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [LV; 4] = [LV::H, LV::L, LV::X, LV::Z];
+
+    /// Every pair of values, repeated so the bits span an inline and a
+    /// heap allocated length.
+    fn pairs(len: usize) -> (PackedBits, PackedBits) {
+        let a = (0..len).map(|i| ALL[i % 4].clone()).collect();
+        let b = (0..len).map(|i| ALL[i / 4 % 4].clone()).collect();
+        (a, b)
+    }
+
+    #[test]
+    fn test_word_ops_match_lv() {
+        for len in [16, 100, 300] {
+            let (a, b) = pairs(len);
+            let (and, or, not, resolved) = (a.and(&b), a.or(&b), a.not(), a.resolve(&b));
+            let xor = a.xor(&b);
+            for i in 0..len {
+                let (x, y) = (a.get(i), b.get(i));
+                assert_eq!(and.get(i), x.and(&y));
+                assert_eq!(or.get(i), x.or(&y));
+                assert_eq!(not.get(i), x.not());
+                assert_eq!(resolved.get(i), x.resolve(&y));
+                assert_eq!(xor.get(i), x.xor(&y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unused_bits_stay_clear() {
+        let z = PackedBits::filled(70, LV::Z);
+        let ones = PackedBits::filled(70, LV::L).not();
+        assert_eq!(z.not(), PackedBits::filled(70, LV::X));
+        assert_eq!(ones, (0..70).map(|_| LV::H).collect());
+        assert_ne!(PackedBits::filled(3, LV::L), PackedBits::filled(4, LV::L));
+    }
+}
@@ -0,0 +1,234 @@
+//! Taking `Bits` apart and putting them together. Index 0 is the least
+//! significant bit throughout: ranges run from low to high bits, iterators
+//! start at bit 0 and `concat` puts later parts on top.
+
+use std::{
+    fmt::Display,
+    ops::{Index, Range},
+};
+
+use super::{Bits, LogicValue, LV};
+
+/// A range that doesn't lie within the bits it was taken from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeError {
+    pub start: usize,
+    pub end: usize,
+    pub len: usize,
+}
+
+impl Display for RangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Range {}..{} doesn't lie within {} bits",
+            self.start, self.end, self.len
+        )
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+impl<V: LogicValue> Bits<V> {
+    /// Bits `start..end`, panics if the range lies outside.
+    pub fn slice(&self, start: usize, end: usize) -> Self {
+        self.get_range(start, end)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Bits `start..end`, `end` may be the length to take the top bits.
+    pub fn get_range(&self, start: usize, end: usize) -> Result<Self, RangeError> {
+        if start > end || end > self.len() {
+            return Err(RangeError {
+                start,
+                end,
+                len: self.len(),
+            });
+        }
+        Ok((start..end).map(|i| self.get(i)).collect())
+    }
+
+    /// Joins `high` on top of these bits.
+    pub fn concat(&self, high: &Self) -> Self {
+        self.iter().chain(high.iter()).collect()
+    }
+
+    /// Joins `parts` with the first one lowest, so `{a, b, c}` in Verilog
+    /// is `concat_all([&c, &b, &a])`.
+    pub fn concat_all<'a>(parts: impl IntoIterator<Item = &'a Self>) -> Self
+    where
+        V: 'a,
+    {
+        parts.into_iter().flat_map(|part| part.iter()).collect()
+    }
+
+    /// The bits in opposite order.
+    pub fn reverse(&self) -> Self {
+        self.iter().rev().collect()
+    }
+
+    /// `count` copies on top of each other, `{count{bits}}` in Verilog.
+    pub fn replicate(&self, count: usize) -> Self {
+        (0..count).flat_map(|_| self.iter()).collect()
+    }
+
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter {
+            bits: self,
+            range: 0..self.len(),
+        }
+    }
+
+    /// 1 if all bits are 1, 0 if any is 0, unknown otherwise. 1 when empty.
+    pub fn and_reduce(&self) -> V {
+        self.iter().fold(V::from_bool(true), |acc, v| acc.and(&v))
+    }
+
+    /// 1 if any bit is 1, 0 if all are 0, unknown otherwise. 0 when empty.
+    pub fn or_reduce(&self) -> V {
+        self.iter().fold(V::from_bool(false), |acc, v| acc.or(&v))
+    }
+}
+
+impl Bits {
+    /// The parity, 1 for an odd number of 1 bits. X if any bit is X or Z.
+    pub fn xor_reduce(&self) -> LV {
+        match self.to_le_words() {
+            Ok(words) => LV::from(words.iter().map(|w| w.count_ones()).sum::<u32>() % 2 == 1),
+            Err(_) => LV::X,
+        }
+    }
+}
+
+impl Index<usize> for Bits {
+    type Output = LV;
+
+    /// Like `get`, panics if `idx` is out of range.
+    fn index(&self, idx: usize) -> &LV {
+        match self.get(idx) {
+            LV::H => &LV::H,
+            LV::L => &LV::L,
+            LV::X => &LV::X,
+            LV::Z => &LV::Z,
+        }
+    }
+}
+
+/// The values of `Bits`, from bit 0 up.
+pub struct Iter<'a, V: LogicValue> {
+    bits: &'a Bits<V>,
+    range: Range<usize>,
+}
+
+impl<V: LogicValue> Iterator for Iter<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        self.range.next().map(|i| self.bits.get(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<V: LogicValue> DoubleEndedIterator for Iter<'_, V> {
+    fn next_back(&mut self) -> Option<V> {
+        self.range.next_back().map(|i| self.bits.get(i))
+    }
+}
+
+impl<V: LogicValue> ExactSizeIterator for Iter<'_, V> {}
+
+impl<'a, V: LogicValue> IntoIterator for &'a Bits<V> {
+    type Item = V;
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Iter<'a, V> {
+        self.iter()
+    }
+}
+
+/// The values of owned `Bits`, from bit 0 up.
+pub struct IntoIter<V: LogicValue> {
+    bits: Bits<V>,
+    range: Range<usize>,
+}
+
+impl<V: LogicValue> Iterator for IntoIter<V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        self.range.next().map(|i| self.bits.get(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<V: LogicValue> DoubleEndedIterator for IntoIter<V> {
+    fn next_back(&mut self) -> Option<V> {
+        self.range.next_back().map(|i| self.bits.get(i))
+    }
+}
+
+impl<V: LogicValue> ExactSizeIterator for IntoIter<V> {}
+
+impl<V: LogicValue> IntoIterator for Bits<V> {
+    type Item = V;
+    type IntoIter = IntoIter<V>;
+
+    fn into_iter(self) -> IntoIter<V> {
+        let range = 0..self.len();
+        IntoIter { bits: self, range }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Bits {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_slicing() {
+        let b = parse("8'b1100_1010");
+        assert_eq!(b.get_range(4, 8), Ok(parse("4'b1100")));
+        assert_eq!(
+            b.get_range(6, 9).unwrap_err().to_string(),
+            "Range 6..9 doesn't lie within 8 bits"
+        );
+        assert_eq!(b.reverse(), parse("8'b0101_0011"));
+        assert_eq!(parse("2'b10").replicate(3), parse("6'b101010"));
+        let (hi, lo) = (parse("3'b111"), parse("2'b00"));
+        assert_eq!(Bits::concat_all([&lo, &hi, &lo]), parse("7'b0011100"));
+        assert_eq!(b[1], LV::H);
+        assert_eq!(b.iter().next_back(), Some(LV::H));
+        let collected: Vec<LV> = b.clone().into_iter().collect();
+        assert_eq!(collected.len(), 8);
+        assert_eq!((&b).into_iter().filter(|lv| *lv == LV::H).count(), 4);
+    }
+
+    #[test]
+    fn test_reductions() {
+        for (literal, and, or, xor) in [
+            ("4'b1111", LV::H, LV::H, LV::L),
+            ("4'b0111", LV::L, LV::H, LV::H),
+            ("4'b0000", LV::L, LV::L, LV::L),
+            ("4'b1x11", LV::X, LV::H, LV::X),
+            ("4'b0z00", LV::L, LV::X, LV::X),
+        ] {
+            let b = parse(literal);
+            assert_eq!(
+                (b.and_reduce(), b.or_reduce(), b.xor_reduce()),
+                (and, or, xor),
+                "{}",
+                literal
+            );
+        }
+        assert_eq!(Bits::<LV>::new(0).and_reduce(), LV::H);
+    }
+}
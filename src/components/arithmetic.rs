@@ -0,0 +1,167 @@
+//! Arithmetic components, all with operands of `width` bits (default 1):
+//! - `adder`: inputs `a`, `b` and `cin`, outputs `sum` and `cout`
+//! - `subtractor`: inputs `a`, `b` and `bin`, outputs `diff` and `bout`
+//! - `comparator`: inputs `a` and `b`, one bit outputs `lt`, `eq` and `gt`;
+//!   compares two's complement numbers if `signed` is set
+//! - `multiplier`: inputs `a` and `b`, outputs the low and high half of the
+//!   product
+//! - `divider`: inputs `a` and `b`, outputs `quotient` and `remainder`, both
+//!   X when dividing by zero
+//!
+//! An unknown operand bit makes every output that depends on it X.
+
+use std::{cmp::Ordering, collections::HashMap, sync::Arc};
+
+use crate::{
+    bits::{Bits, LV},
+    GateConstructor,
+};
+
+use super::width;
+
+fn lv_bits(lv: LV) -> Bits {
+    [lv].into_iter().collect()
+}
+
+/// Two `width` bit operands plus `extra_in` one bit inputs, with the given
+/// output widths relative to the operand width.
+fn binary_op(
+    extra_in: usize,
+    outputs: &'static [Option<usize>],
+    op: fn(&[Bits], bool) -> Vec<Bits>,
+) -> GateConstructor {
+    GateConstructor {
+        init: Box::new(move |gpio, params, cm| {
+            gpio.add_in(width(params), cm);
+            gpio.add_in(width(params), cm);
+            for _ in 0..extra_in {
+                gpio.add_in(1, cm);
+            }
+            for out in outputs {
+                gpio.add_out(out.unwrap_or(width(params)), cm);
+            }
+        }),
+        update: Arc::new(Box::new(move |data, dispatch_output_update| {
+            let signed = data.params.get_bool("signed", false);
+            for (i, value) in op(&data.in_values, signed).into_iter().enumerate() {
+                dispatch_output_update(1, i, value);
+            }
+        })),
+        initial_update: false,
+    }
+}
+
+pub(crate) fn register(constructors: &mut HashMap<&'static str, GateConstructor>) {
+    constructors.insert(
+        "adder",
+        binary_op(1, &[None, Some(1)], |v, _| {
+            let (sum, carry) = v[0].add(&v[1], &v[2].get(0));
+            vec![sum, lv_bits(carry)]
+        }),
+    );
+    constructors.insert(
+        "subtractor",
+        binary_op(1, &[None, Some(1)], |v, _| {
+            let (diff, borrow) = v[0].sub(&v[1], &v[2].get(0));
+            vec![diff, lv_bits(borrow)]
+        }),
+    );
+    constructors.insert(
+        "comparator",
+        binary_op(0, &[Some(1), Some(1), Some(1)], |v, signed| {
+            let ordering = if signed {
+                v[0].cmp_signed(&v[1])
+            } else {
+                v[0].cmp_unsigned(&v[1])
+            };
+            let is = |expected| match ordering {
+                Some(o) => LV::from(o == expected),
+                None => LV::X,
+            };
+            // Equality can still be ruled out by a known differing bit
+            let eq = v[0].eq_lv(&v[1]);
+            vec![
+                lv_bits(is(Ordering::Less)),
+                lv_bits(eq),
+                lv_bits(is(Ordering::Greater)),
+            ]
+        }),
+    );
+    constructors.insert(
+        "multiplier",
+        binary_op(0, &[None, None], |v, _| {
+            let w = v[0].len();
+            let product = v[0].mul(&v[1]);
+            let half = |from: usize| (from..from + w).map(|i| product.get(i)).collect();
+            vec![half(0), half(w)]
+        }),
+    );
+    constructors.insert(
+        "divider",
+        binary_op(0, &[None, None], |v, _| {
+            let (quotient, remainder) = v[0].div_rem(&v[1]);
+            vec![quotient, remainder]
+        }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::test_util::eval,
+        params::{Param, Params},
+        ComponentManager,
+    };
+
+    fn params(width: i64, signed: bool) -> Params {
+        Params::new()
+            .with("width", Param::Int(width))
+            .with("signed", Param::Bool(signed))
+    }
+
+    #[test]
+    fn test_adder_and_subtractor() {
+        let mut cm = ComponentManager::new();
+        let adder = cm
+            .create_gate_with_params("adder", params(4, false))
+            .unwrap();
+        assert_eq!(eval(&mut cm, adder, &["0111", "1010", "1"]), ["0010", "1"]);
+        assert_eq!(eval(&mut cm, adder, &["0111", "10X0", "0"]), ["XXXX", "X"]);
+
+        let sub = cm
+            .create_gate_with_params("subtractor", params(4, false))
+            .unwrap();
+        assert_eq!(eval(&mut cm, sub, &["0011", "0101", "0"]), ["1110", "1"]);
+        assert_eq!(eval(&mut cm, sub, &["0101", "0011", "1"]), ["0001", "0"]);
+    }
+
+    #[test]
+    fn test_comparator() {
+        let mut cm = ComponentManager::new();
+        let unsigned = cm
+            .create_gate_with_params("comparator", params(4, false))
+            .unwrap();
+        let signed = cm
+            .create_gate_with_params("comparator", params(4, true))
+            .unwrap();
+        assert_eq!(eval(&mut cm, unsigned, &["0011", "1111"]), ["1", "0", "0"]);
+        assert_eq!(eval(&mut cm, signed, &["0011", "1111"]), ["0", "0", "1"]);
+        assert_eq!(eval(&mut cm, unsigned, &["0110", "0110"]), ["0", "1", "0"]);
+        assert_eq!(eval(&mut cm, unsigned, &["X110", "0111"]), ["X", "0", "X"]);
+    }
+
+    #[test]
+    fn test_multiplier_and_divider() {
+        let mut cm = ComponentManager::new();
+        let mul = cm
+            .create_gate_with_params("multiplier", params(4, false))
+            .unwrap();
+        assert_eq!(eval(&mut cm, mul, &["1101", "1011"]), ["1111", "1000"]);
+
+        let div = cm
+            .create_gate_with_params("divider", params(4, false))
+            .unwrap();
+        assert_eq!(eval(&mut cm, div, &["1110", "0100"]), ["0011", "0010"]);
+        assert_eq!(eval(&mut cm, div, &["1110", "0000"]), ["XXXX", "XXXX"]);
+    }
+}
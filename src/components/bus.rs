@@ -0,0 +1,277 @@
+//! Drivers for lumps shared by several outputs, all `width` bits wide
+//! (default 1).
+//!
+//! - `tristate`: inputs `d` and `en`, output `y`; drives `d` while `en` is
+//!   high and floats while it is low
+//! - `open_drain` (also `open_collector`): input `d`, output `y`; pulls low
+//!   where `d` is 0 and floats where it is 1, so several of them and a
+//!   pull-up form a wired AND
+//! - `pull_up`, `pull_down`: output `y`, weakly driven to all ones or zeros
+//! - `vcc`, `gnd`: output `y`, all ones or zeros with supply strength
+//! - `bus_keeper`: inout `data`, weakly holds the last level the lump had
+//!   when nothing else drives it
+//!
+//! On a lump the strongest drivers win, see `bits::resolve_drivers`, so
+//! anything overrides a pull and nothing overrides a supply.
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    bits::{Bits, Strength, LV},
+    GateConstructor,
+};
+
+use super::{sequential::choose, width};
+
+fn fixed_level(level: LV, strength: Strength) -> GateConstructor {
+    GateConstructor {
+        init: Box::new(move |gpio, params, cm| {
+            gpio.add_out_with_strength(width(params), strength, cm);
+        }),
+        update: Arc::new(Box::new(move |data, dispatch_output_update| {
+            let value = (0..width(data.params)).map(|_| level.clone()).collect();
+            dispatch_output_update(0, 0, value);
+        })),
+        initial_update: true,
+    }
+}
+
+fn open_drain() -> GateConstructor {
+    GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            gpio.add_in(width(params), cm);
+            gpio.add_out(width(params), cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let d = &data.in_values[0];
+            let y = (0..d.len())
+                .map(|i| match d.get(i) {
+                    LV::L => LV::L,
+                    LV::H => LV::Z,
+                    _ => LV::X,
+                })
+                .collect();
+            dispatch_output_update(1, 0, y);
+        })),
+        initial_update: false,
+    }
+}
+
+pub(crate) fn register(constructors: &mut HashMap<&'static str, GateConstructor>) {
+    let tristate = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            gpio.add_in(width(params), cm);
+            gpio.add_in(1, cm);
+            gpio.add_out(width(params), cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let d = data.in_values[0].clone();
+            let floating = Bits::new(d.len());
+            let y = choose(data.in_values[1].get(0), d, floating);
+            dispatch_output_update(1, 0, y);
+        })),
+        initial_update: false,
+    };
+    constructors.insert("tristate", tristate);
+    constructors.insert("open_drain", open_drain());
+    constructors.insert("open_collector", open_drain());
+    constructors.insert("pull_up", fixed_level(LV::H, Strength::Weak));
+    constructors.insert("pull_down", fixed_level(LV::L, Strength::Weak));
+    constructors.insert("vcc", fixed_level(LV::H, Strength::Supply));
+    constructors.insert("gnd", fixed_level(LV::L, Strength::Supply));
+
+    let bus_keeper = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            gpio.add_in_out_with_strength(width(params), Strength::Weak, cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let level = data.inout_values[0].0.clone();
+            dispatch_output_update(0, 0, level);
+        })),
+        initial_update: false,
+    };
+    constructors.insert("bus_keeper", bus_keeper);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::test_util::{bits, show},
+        run::RunBudget,
+        ComponentManager, PinType,
+    };
+
+    fn settle(cm: &mut ComponentManager) {
+        cm.run_budgeted(&RunBudget::default());
+    }
+
+    fn attach(cm: &mut ComponentManager, gate: usize, pin_type: PinType, lump: usize) {
+        cm.connect_gate_pin_to_lump(&gate, &0, &pin_type, &lump)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_tristate_bus() {
+        let mut cm = ComponentManager::new();
+        let bus = cm.create_lump(1);
+        let buffers = [
+            cm.create_gate("tristate").unwrap(),
+            cm.create_gate("tristate").unwrap(),
+        ];
+        // Enable first, so the old `d` doesn't reach the bus on the way
+        let drive = |cm: &mut ComponentManager, gate: usize, d: &str, en: &str| {
+            let pins = cm.try_get_gate_pins(&gate, &PinType::IN).unwrap();
+            cm.schedule_pin_update(0, pins[1], bits(en));
+            cm.schedule_pin_update(0, pins[0], bits(d));
+            settle(cm);
+        };
+        for buffer in buffers {
+            attach(&mut cm, buffer, PinType::OUT, bus);
+        }
+        drive(&mut cm, buffers[0], "1", "1");
+        drive(&mut cm, buffers[1], "0", "0");
+        assert_eq!(show(&cm.get_lump_value(&bus)), "1");
+        drive(&mut cm, buffers[1], "0", "1");
+        assert_eq!(show(&cm.get_lump_value(&bus)), "X");
+        assert_eq!(cm.lump_conflicts(&bus).unwrap(), [0]);
+        drive(&mut cm, buffers[0], "1", "0");
+        assert_eq!(show(&cm.get_lump_value(&bus)), "0");
+        drive(&mut cm, buffers[1], "0", "0");
+        assert_eq!(show(&cm.get_lump_value(&bus)), "Z");
+    }
+
+    #[test]
+    fn test_wired_and_with_pull_up() {
+        let mut cm = ComponentManager::new();
+        let line = cm.create_lump(1);
+        let pull_up = cm.create_gate("pull_up").unwrap();
+        let drains = [
+            cm.create_gate("open_drain").unwrap(),
+            cm.create_gate("open_collector").unwrap(),
+        ];
+        attach(&mut cm, pull_up, PinType::OUT, line);
+        for drain in drains {
+            attach(&mut cm, drain, PinType::OUT, line);
+        }
+        let set = |cm: &mut ComponentManager, levels: [&str; 2]| {
+            for (drain, level) in drains.iter().zip(levels) {
+                let pin = cm.try_get_gate_pins(drain, &PinType::IN).unwrap()[0];
+                cm.schedule_pin_update(0, pin, bits(level));
+            }
+            settle(cm);
+            show(&cm.get_lump_value(&line))
+        };
+        assert_eq!(set(&mut cm, ["1", "1"]), "1");
+        assert_eq!(set(&mut cm, ["0", "1"]), "0");
+        assert_eq!(set(&mut cm, ["1", "0"]), "0");
+        assert_eq!(set(&mut cm, ["1", "1"]), "1");
+    }
+
+    #[test]
+    fn test_bus_keeper_holds_level() {
+        let mut cm = ComponentManager::new();
+        let bus = cm.create_lump(1);
+        let keeper = cm.create_gate("bus_keeper").unwrap();
+        let buffer = cm.create_gate("tristate").unwrap();
+        attach(&mut cm, keeper, PinType::INOUT, bus);
+        attach(&mut cm, buffer, PinType::OUT, bus);
+        let pins = cm.try_get_gate_pins(&buffer, &PinType::IN).unwrap();
+        for (d, en, expected) in [
+            ("1", "1", "1"),
+            ("1", "0", "1"),
+            ("0", "1", "0"),
+            ("1", "0", "0"),
+        ] {
+            cm.schedule_pin_update(0, pins[1], bits(en));
+            cm.schedule_pin_update(0, pins[0], bits(d));
+            settle(&mut cm);
+            assert_eq!(show(&cm.get_lump_value(&bus)), expected);
+        }
+
+        let pull_down = cm.create_gate("pull_down").unwrap();
+        attach(&mut cm, pull_down, PinType::OUT, bus);
+        settle(&mut cm);
+        assert_eq!(show(&cm.get_lump_value(&bus)), "0");
+    }
+
+    #[test]
+    fn test_supply_overrides_drivers() {
+        let mut cm = ComponentManager::new();
+        let rail = cm.create_lump(1);
+        let vcc = cm.create_gate("vcc").unwrap();
+        let driver = cm.create_gate("input").unwrap();
+        attach(&mut cm, vcc, PinType::OUT, rail);
+        attach(&mut cm, driver, PinType::OUT, rail);
+        settle(&mut cm);
+        assert_eq!(show(&cm.get_lump_value(&rail)), "1");
+        assert!(cm.lump_conflicts(&rail).unwrap().is_empty());
+
+        let gnd = cm.create_gate("gnd").unwrap();
+        attach(&mut cm, gnd, PinType::OUT, rail);
+        settle(&mut cm);
+        assert_eq!(show(&cm.get_lump_value(&rail)), "X");
+        assert_eq!(cm.lump_conflicts(&rail).unwrap(), [0]);
+    }
+
+    #[test]
+    fn test_removing_drivers() {
+        let mut cm = ComponentManager::new();
+        let rail = cm.create_lump(1);
+        let vcc = cm.create_gate("vcc").unwrap();
+        let gnd = cm.create_gate("gnd").unwrap();
+        let not = cm.create_gate("not").unwrap();
+        attach(&mut cm, vcc, PinType::OUT, rail);
+        attach(&mut cm, gnd, PinType::OUT, rail);
+        attach(&mut cm, not, PinType::IN, rail);
+        settle(&mut cm);
+        assert_eq!(cm.lump_conflicts(&rail).unwrap(), [0]);
+
+        cm.delete_gate(&gnd).unwrap();
+        settle(&mut cm);
+        assert_eq!(show(&cm.get_lump_value(&rail)), "1");
+        assert!(cm.lump_conflicts(&rail).unwrap().is_empty());
+        let out = cm.try_get_gate_pins(&not, &PinType::OUT).unwrap()[0];
+        assert_eq!(show(&cm.get_pin_value(&out)), "0");
+
+        // Without drivers the lump floats and the reader sees it
+        cm.delete_gate(&vcc).unwrap();
+        settle(&mut cm);
+        assert_eq!(show(&cm.get_lump_value(&rail)), "Z");
+        assert_eq!(show(&cm.get_pin_value(&out)), "X");
+    }
+
+    #[test]
+    fn test_connecting_settled_pins() {
+        // Connect then drive
+        let mut cm = ComponentManager::new();
+        let rail = cm.create_lump(1);
+        let not = cm.create_gate("not").unwrap();
+        let vcc = cm.create_gate("vcc").unwrap();
+        attach(&mut cm, not, PinType::IN, rail);
+        attach(&mut cm, vcc, PinType::OUT, rail);
+        settle(&mut cm);
+        let out = cm.try_get_gate_pins(&not, &PinType::OUT).unwrap()[0];
+        assert_eq!(show(&cm.get_pin_value(&out)), "0");
+
+        // Drive then connect, both the settled driver and the new reader
+        let mut cm = ComponentManager::new();
+        let rail = cm.create_lump(1);
+        let gnd = cm.create_gate("gnd").unwrap();
+        let not = cm.create_gate("not").unwrap();
+        settle(&mut cm);
+        attach(&mut cm, gnd, PinType::OUT, rail);
+        assert_eq!(show(&cm.get_lump_value(&rail)), "0");
+        attach(&mut cm, not, PinType::IN, rail);
+        settle(&mut cm);
+        let out = cm.try_get_gate_pins(&not, &PinType::OUT).unwrap()[0];
+        assert_eq!(show(&cm.get_pin_value(&out)), "1");
+
+        // Moving the driver takes its value along
+        let other = cm.create_lump(1);
+        attach(&mut cm, gnd, PinType::OUT, other);
+        settle(&mut cm);
+        assert_eq!(show(&cm.get_lump_value(&other)), "0");
+        assert_eq!(show(&cm.get_lump_value(&rail)), "Z");
+        assert_eq!(show(&cm.get_pin_value(&out)), "X");
+    }
+}
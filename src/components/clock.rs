@@ -0,0 +1,155 @@
+//! Clock generator.
+//!
+//! Parameters:
+//! - `period`: length of one cycle, at least 2 (default 2)
+//! - `duty`: high time in percent of the period (default 50)
+//! - `phase`: delay before the first rising edge (default 0)
+//! - `enabled`: setting this to `false` with `set_gate_param` stops the
+//!   clock, setting it back to `true` restarts it (default `true`)
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{bits::Bits, params::Param, GateConstructor};
+
+pub(crate) fn register(constructors: &mut HashMap<&'static str, GateConstructor>) {
+    let clock = GateConstructor {
+        init: Box::new(|gpio, _, cm| {
+            gpio.add_out(1, cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let period = data.params.get_int("period", 2).max(2) as u64;
+            let duty = data.params.get_int("duty", 50).clamp(0, 100) as u64;
+            let high_time = (period * duty / 100).clamp(1, period - 1);
+            let phase = data.params.get_int("phase", 0).max(0) as u64;
+
+            if !data.params.get_bool("enabled", true) {
+                data.state.remove("next_edge");
+                return;
+            }
+            if data.state.get_int("next_edge", -1) < 0 {
+                // (Re)start: low until the first rising edge
+                data.state.set("high", Param::Bool(false));
+                data.state
+                    .set("next_edge", Param::Int((data.time + phase) as i64));
+                if phase > 0 {
+                    dispatch_output_update(0, 0, Bits::new(1).set_num(0));
+                    data.schedule_wakeup(phase);
+                    return;
+                }
+            }
+            // Only the wakeup for the scheduled edge toggles, others are left
+            // over from a stopped clock or come from a parameter change
+            if data.state.get_int("next_edge", -1) as u64 == data.time {
+                let high = !data.state.get_bool("high", false);
+                data.state.set("high", Param::Bool(high));
+                dispatch_output_update(0, 0, Bits::new(1).set_num(high as i64));
+                let wait = if high { high_time } else { period - high_time };
+                data.state
+                    .set("next_edge", Param::Int((data.time + wait) as i64));
+                data.schedule_wakeup(wait);
+            }
+        })),
+        initial_update: true,
+    };
+    constructors.insert("clock", clock);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        notify::{Notification, Watch},
+        params::{Param, Params},
+        run::RunBudget,
+        ComponentManager, PinType,
+    };
+
+    fn record_edges(cm: &mut ComponentManager, clock: usize) -> Rc<RefCell<Vec<(u64, String)>>> {
+        let lump = cm.create_lump(1);
+        cm.connect_gate_pin_to_lump(&clock, &0, &PinType::OUT, &lump)
+            .unwrap();
+        let edges = Rc::new(RefCell::new(Vec::new()));
+        let sink = edges.clone();
+        cm.subscribe(Watch::Lump(lump), move |n| {
+            if let Notification::LumpChanged { time, value, .. } = n {
+                sink.borrow_mut().push((*time, value.to_string()));
+            }
+        });
+        edges
+    }
+
+    fn run_until(cm: &mut ComponentManager, time: u64) {
+        cm.run_budgeted(&RunBudget {
+            until_time: Some(time),
+            ..Default::default()
+        });
+    }
+
+    fn edges(list: &[(u64, &str)]) -> Vec<(u64, String)> {
+        list.iter().map(|(t, v)| (*t, v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_independent_clocks() {
+        let mut cm = ComponentManager::new();
+        let fast = cm.create_gate("clock").unwrap();
+        let slow = cm
+            .create_gate_with_params(
+                "clock",
+                Params::new()
+                    .with("period", Param::Int(8))
+                    .with("duty", Param::Int(25))
+                    .with("phase", Param::Int(3)),
+            )
+            .unwrap();
+        let fast_edges = record_edges(&mut cm, fast);
+        let slow_edges = record_edges(&mut cm, slow);
+        run_until(&mut cm, 13);
+
+        assert_eq!(
+            *fast_edges.borrow(),
+            edges(&[
+                (0, "1"),
+                (1, "0"),
+                (2, "1"),
+                (3, "0"),
+                (4, "1"),
+                (5, "0"),
+                (6, "1"),
+                (7, "0"),
+                (8, "1"),
+                (9, "0"),
+                (10, "1"),
+                (11, "0"),
+                (12, "1"),
+                (13, "0"),
+            ])
+        );
+        assert_eq!(
+            *slow_edges.borrow(),
+            edges(&[(0, "0"), (3, "1"), (5, "0"), (11, "1"), (13, "0")])
+        );
+    }
+
+    #[test]
+    fn test_stop_and_restart() {
+        let mut cm = ComponentManager::new();
+        let clock = cm
+            .create_gate_with_params("clock", Params::new().with("period", Param::Int(4)))
+            .unwrap();
+        let seen = record_edges(&mut cm, clock);
+        run_until(&mut cm, 5);
+        cm.set_gate_param(&clock, "enabled", Param::Bool(false))
+            .unwrap();
+        run_until(&mut cm, 20);
+        cm.set_gate_param(&clock, "enabled", Param::Bool(true))
+            .unwrap();
+        run_until(&mut cm, 22);
+
+        assert_eq!(
+            *seen.borrow(),
+            edges(&[(0, "1"), (2, "0"), (4, "1"), (22, "0")])
+        );
+    }
+}
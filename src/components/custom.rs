@@ -0,0 +1,107 @@
+//! Components defined at runtime instead of in Rust, see
+//! `ComponentManager::register_truth_table` and
+//! `ComponentManager::register_equations`.
+
+use std::sync::Arc;
+
+use crate::{
+    bits::Bits,
+    expr::Equations,
+    truth_table::{evaluate_expanded, TruthTable},
+    GateConstructor,
+};
+
+/// A gate with one 1-bit input per table input and one 1-bit output per
+/// table output, in declaration order.
+pub(crate) fn truth_table(table: &TruthTable) -> Result<GateConstructor, String> {
+    let expanded: Vec<Bits> = table
+        .expand()?
+        .iter()
+        .map(|outputs| outputs.iter().map(|e| e.to_lv()).collect())
+        .collect();
+    let (inputs, outputs) = (table.inputs().len(), table.outputs().len());
+    Ok(GateConstructor {
+        init: Box::new(move |gpio, _, cm| {
+            for _ in 0..inputs {
+                gpio.add_in(1, cm);
+            }
+            for _ in 0..outputs {
+                gpio.add_out(1, cm);
+            }
+        }),
+        update: Arc::new(Box::new(move |data, dispatch_output_update| {
+            let levels: Vec<_> = data.in_values.iter().map(|b| b.get(0)).collect();
+            let result = evaluate_expanded(&expanded, &levels);
+            for out in 0..outputs {
+                dispatch_output_update(1, out, result.slice(out, out + 1));
+            }
+        })),
+        // Without inputs nothing would ever trigger it
+        initial_update: inputs == 0,
+    })
+}
+
+/// A gate with one pin per declared input and output, as wide as the
+/// signal, in declaration order.
+pub(crate) fn equations(equations: &Equations) -> GateConstructor {
+    let equations = Arc::new(equations.clone());
+    let ports = equations.clone();
+    // Without inputs nothing would ever trigger it
+    let initial_update = equations.inputs().is_empty();
+    GateConstructor {
+        init: Box::new(move |gpio, _, cm| {
+            for (_, width) in ports.inputs() {
+                gpio.add_in(*width, cm);
+            }
+            for (_, width) in ports.outputs() {
+                gpio.add_out(*width, cm);
+            }
+        }),
+        update: Arc::new(Box::new(move |data, dispatch_output_update| {
+            for (out, value) in equations.evaluate(&data.in_values).into_iter().enumerate() {
+                dispatch_output_update(1, out, value);
+            }
+        })),
+        initial_update,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::test_util::eval, expr::Equations, truth_table::TruthTable, ComponentManager,
+    };
+
+    #[test]
+    fn test_truth_table_component() {
+        let mut cm = ComponentManager::new();
+        let mut table = TruthTable::new(&["a", "b"], &["y", "n"]).unwrap();
+        table.add_row("11", "10").unwrap();
+        table.add_row("0-", "01").unwrap();
+        table.add_row("-0", "01").unwrap();
+        cm.register_truth_table("and_nand", &table).unwrap();
+        assert!(cm.register_truth_table("and_nand", &table).is_err());
+        assert!(cm.register_truth_table("and", &table).is_err());
+
+        let gate = cm.create_gate("and_nand").unwrap();
+        assert_eq!(eval(&mut cm, gate, &["1", "1"]), ["1", "0"]);
+        assert_eq!(eval(&mut cm, gate, &["0", "X"]), ["0", "1"]);
+        assert_eq!(eval(&mut cm, gate, &["1", "X"]), ["X", "X"]);
+    }
+
+    #[test]
+    fn test_equations_component() {
+        let mut cm = ComponentManager::new();
+        let equations = Equations::parse(
+            "input [3:0] a, b; input cin; output [3:0] y; output carry;
+             y = a ^ b; carry = &a | cin",
+        )
+        .unwrap();
+        cm.register_equations("xor4", &equations).unwrap();
+        assert!(cm.register_equations("xor4", &equations).is_err());
+
+        let gate = cm.create_gate("xor4").unwrap();
+        assert_eq!(eval(&mut cm, gate, &["1100", "1010", "0"]), ["0110", "0"]);
+        assert_eq!(eval(&mut cm, gate, &["1111", "10X0", "X"]), ["01X1", "1"]);
+    }
+}
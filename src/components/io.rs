@@ -0,0 +1,219 @@
+//! Top level inputs and outputs of a circuit.
+//!
+//! Sources drive their output as soon as they are added to the netlist:
+//! - `constant`: drives `value` (a number or `Bits`) on `width` bits
+//! - `switch`: 1 bit, flipped with `toggle_switch`
+//! - `button`: 1 bit, high between `press_button` and `release_button`
+//! - `input`: `width` bits set with `set_input`, starting at `value` or 0
+//!
+//! Sinks have a single input that is read with `read_output`:
+//! - `probe`: `width` bits
+//! - `led`: 1 bit
+//!
+//! Any of them can be given a `label` parameter to find it again with
+//! `find_gate_by_label`.
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    bits::{Bits, LV},
+    params::{Param, Params},
+    ComponentManager, GPIOHandler, GateConstructor,
+};
+
+use super::width;
+
+fn source(width_of: fn(&Params) -> usize) -> GateConstructor {
+    GateConstructor {
+        init: Box::new(move |gpio, params, cm| {
+            gpio.add_out(width_of(params), cm);
+        }),
+        update: Arc::new(Box::new(move |data, dispatch_output_update| {
+            let width = width_of(data.params);
+            let value = data
+                .state
+                .get_bits("value", width)
+                .or_else(|| data.params.get_bits("value", width))
+                .unwrap_or_else(|| Bits::new(width).set_num(0));
+            dispatch_output_update(0, 0, value);
+        })),
+        initial_update: true,
+    }
+}
+
+fn sink(width_of: fn(&Params) -> usize) -> GateConstructor {
+    GateConstructor {
+        init: Box::new(move |gpio, params, cm| {
+            gpio.add_in(width_of(params), cm);
+        }),
+        update: Arc::new(Box::new(|_, _| {})),
+        initial_update: false,
+    }
+}
+
+pub(crate) fn register(constructors: &mut HashMap<&'static str, GateConstructor>) {
+    constructors.insert("constant", source(width));
+    constructors.insert("switch", source(|_| 1));
+    constructors.insert("button", source(|_| 1));
+    constructors.insert("input", source(width));
+    constructors.insert("probe", sink(width));
+    constructors.insert("led", sink(|_| 1));
+}
+
+impl ComponentManager {
+    /// Sets the value driven by an `input`, `switch` or `button`.
+    pub fn set_input(&self, gate_id: &usize, value: Bits) -> Result<(), String> {
+        self.write_source(gate_id, &["input", "switch", "button"], value)
+    }
+
+    pub fn toggle_switch(&self, gate_id: &usize) -> Result<(), String> {
+        let on = self.read_source(gate_id, &["switch"])?.get(0) == LV::H;
+        self.write_source(gate_id, &["switch"], Bits::new(1).set_num(!on as i64))
+    }
+
+    pub fn press_button(&self, gate_id: &usize) -> Result<(), String> {
+        self.write_source(gate_id, &["button"], Bits::new(1).set_num(1))
+    }
+
+    pub fn release_button(&self, gate_id: &usize) -> Result<(), String> {
+        self.write_source(gate_id, &["button"], Bits::new(1).set_num(0))
+    }
+
+    /// Reads the value arriving at a `probe` or `led`.
+    pub fn read_output(&self, gate_id: &usize) -> Result<Bits, String> {
+        let pin_id = self.io_pin(gate_id, &["probe", "led"], |gpio| &gpio.in_pins)?;
+        self.try_get_pin_value(&pin_id)
+    }
+
+    fn io_pin(
+        &self,
+        gate_id: &usize,
+        kinds: &[&str],
+        pins: fn(&GPIOHandler) -> &Vec<usize>,
+    ) -> Result<usize, String> {
+        let gates = self.gates.read().unwrap();
+        let gate = gates
+            .get(gate_id)
+            .ok_or(format!("The gate with id {} doesn't exist", gate_id))?;
+        if !kinds.contains(&gate.kind.as_str()) {
+            return Err(format!(
+                "Gate {} is a {} but should be one of {:?}",
+                gate_id, gate.kind, kinds
+            ));
+        }
+        Ok(pins(&gate.gpio)[0])
+    }
+
+    fn read_source(&self, gate_id: &usize, kinds: &[&str]) -> Result<Bits, String> {
+        let pin_id = self.io_pin(gate_id, kinds, |gpio| &gpio.out_pins)?;
+        let width = self.try_get_pin_value(&pin_id)?.len();
+        let gates = self.gates.read().unwrap();
+        let gate = &gates[gate_id];
+        let value = gate.state.read().unwrap().get_bits("value", width);
+        Ok(value
+            .or_else(|| gate.params.get_bits("value", width))
+            .unwrap_or_else(|| Bits::new(width).set_num(0)))
+    }
+
+    fn write_source(&self, gate_id: &usize, kinds: &[&str], value: Bits) -> Result<(), String> {
+        let pin_id = self.io_pin(gate_id, kinds, |gpio| &gpio.out_pins)?;
+        let width = self.try_get_pin_value(&pin_id)?.len();
+        if width != value.len() {
+            return Err(format!(
+                "Gate {} is {} bits wide but got {} bits",
+                gate_id,
+                width,
+                value.len()
+            ));
+        }
+        self.gates.read().unwrap()[gate_id]
+            .state
+            .write()
+            .unwrap()
+            .set("value", Param::Bits(value));
+        self.schedule_gate_wakeup(0, *gate_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bits::Bits,
+        params::{Param, Params},
+        run::RunBudget,
+        ComponentManager, PinType,
+    };
+
+    fn wire(cm: &mut ComponentManager, from: usize, to: usize, to_idx: usize, width: usize) {
+        let lump = cm.create_lump(width);
+        cm.connect_gate_pin_to_lump(&from, &0, &PinType::OUT, &lump)
+            .unwrap();
+        cm.connect_gate_pin_to_lump(&to, &to_idx, &PinType::IN, &lump)
+            .unwrap();
+    }
+
+    fn settle(cm: &mut ComponentManager) {
+        cm.run_budgeted(&RunBudget::default());
+    }
+
+    #[test]
+    fn test_switch_and_button_drive_led() {
+        let mut cm = ComponentManager::new();
+        let switch = cm.create_gate("switch").unwrap();
+        let button = cm.create_gate("button").unwrap();
+        let and = cm.create_gate("and").unwrap();
+        let led = cm
+            .create_gate_with_params(
+                "led",
+                Params::new().with("label", Param::Text("out".into())),
+            )
+            .unwrap();
+        wire(&mut cm, switch, and, 0, 1);
+        wire(&mut cm, button, and, 1, 1);
+        wire(&mut cm, and, led, 0, 1);
+        let led = cm.find_gate_by_label("out").unwrap();
+
+        settle(&mut cm);
+        assert_eq!(cm.read_output(&led).unwrap(), Bits::new(1).set_num(0));
+        cm.toggle_switch(&switch).unwrap();
+        cm.press_button(&button).unwrap();
+        settle(&mut cm);
+        assert_eq!(cm.read_output(&led).unwrap(), Bits::new(1).set_num(1));
+        cm.release_button(&button).unwrap();
+        settle(&mut cm);
+        assert_eq!(cm.read_output(&led).unwrap(), Bits::new(1).set_num(0));
+
+        assert!(cm.press_button(&switch).is_err());
+        assert!(cm.read_output(&and).is_err());
+    }
+
+    #[test]
+    fn test_multi_bit_input_and_constant() {
+        let mut cm = ComponentManager::new();
+        let byte = Params::new().with("width", Param::Int(8));
+        let input = cm.create_gate_with_params("input", byte.clone()).unwrap();
+        let constant = cm
+            .create_gate_with_params("constant", byte.clone().with("value", Param::Int(0x5a)))
+            .unwrap();
+        let probe_in = cm.create_gate_with_params("probe", byte.clone()).unwrap();
+        let probe_const = cm.create_gate_with_params("probe", byte).unwrap();
+        wire(&mut cm, input, probe_in, 0, 8);
+        wire(&mut cm, constant, probe_const, 0, 8);
+
+        settle(&mut cm);
+        assert_eq!(cm.read_output(&probe_in).unwrap(), Bits::new(8).set_num(0));
+        assert_eq!(
+            cm.read_output(&probe_const).unwrap(),
+            Bits::new(8).set_num(0x5a)
+        );
+        cm.set_input(&input, Bits::new(8).set_num(0xc3)).unwrap();
+        settle(&mut cm);
+        assert_eq!(
+            cm.read_output(&probe_in).unwrap(),
+            Bits::new(8).set_num(0xc3)
+        );
+        assert!(cm.set_input(&input, Bits::new(4)).is_err());
+        assert!(cm.set_input(&constant, Bits::new(8)).is_err());
+    }
+}
@@ -0,0 +1,442 @@
+//! Memories of `2^addr_bits` words (`addr_bits` from 1 to 16, default 4)
+//! of `width` bits each (default 1).
+//!
+//! - `rom`: input `addr`, output `data`; reads asynchronously
+//! - `ram`: inputs `addr`, `din`, `we` and `en`, output `dout`; while `en`
+//!   is high `dout` shows the addressed word and `we` writes `din` into it,
+//!   while `en` is low `dout` floats
+//! - `sync_ram`: inputs `clk`, `addr`, `din`, `we` and `en`, output `dout`;
+//!   on a rising clock edge with `en` high writes `din` if `we` is high and
+//!   latches the addressed word, after the write, to `dout`
+//!
+//! With `bidirectional` set the RAMs replace `din` and `dout` by a single
+//! inout pin `data`. They drive it with the read word while enabled and not
+//! writing, and leave it floating otherwise so others can drive the word to
+//! write.
+//!
+//! The initial contents come from `contents`, either `Words` or text in the
+//! plain hex format of `memfile::from_hex_text`. Words not given are X, as
+//! in the images `memfile` reads. While simulating, contents are inspected and changed
+//! with `read_memory`, `write_memory`, `load_memory` and `dump_memory`.
+//!
+//! Unknown address bits read the agreement of all words they could address
+//! and make writes leave X wherever the written and the old word differ.
+
+use std::{collections::HashMap, sync::Arc};
+
+use tracing::warn;
+
+use crate::{
+    bits::{Bits, LV},
+    memfile,
+    params::{Param, Params},
+    ComponentManager, GateConstructor, GateUpdateData,
+};
+
+use super::{
+    routing::{candidates, merge},
+    sequential::{choose, clock_edge},
+    width,
+};
+
+const MEMORIES: [&str; 3] = ["rom", "ram", "sync_ram"];
+
+fn addr_bits(params: &Params) -> usize {
+    params.get_int("addr_bits", 4).clamp(1, 16) as usize
+}
+
+fn bidirectional(params: &Params) -> bool {
+    params.get_bool("bidirectional", false)
+}
+
+fn unknown(width: usize) -> Bits {
+    (0..width).map(|_| LV::X).collect()
+}
+
+/// Contents at construction, padded with X. Malformed `contents` text is
+/// logged and ignored.
+fn initial_contents(params: &Params) -> Vec<Bits> {
+    let width = width(params);
+    let size = 1 << addr_bits(params);
+    let given = match params.get("contents") {
+        Some(Param::Words(words)) => words.clone(),
+        Some(Param::Text(text)) => memfile::from_hex_text(text, width, size).unwrap_or_else(|e| {
+            warn!("Ignoring the contents of a memory: {}", e);
+            Vec::new()
+        }),
+        _ => Vec::new(),
+    };
+    given
+        .into_iter()
+        .map(|word| {
+            if word.len() == width {
+                word
+            } else {
+                unknown(width)
+            }
+        })
+        .chain(std::iter::repeat(unknown(width)))
+        .take(size)
+        .collect()
+}
+
+/// Takes the contents out of the gate state, creating them on first use.
+fn take_memory(params: &Params, state: &mut Params) -> Vec<Bits> {
+    match state.remove("memory") {
+        Some(Param::Words(words)) => words,
+        _ => initial_contents(params),
+    }
+}
+
+fn read(memory: &[Bits], addr: &Bits, width: usize) -> Bits {
+    let words = candidates(addr).into_iter().map(|a| &memory[a]);
+    merge(words, width)
+}
+
+/// Writes `value` if `we` is high, keeps bits that agree with it if `we` is
+/// unknown.
+fn write(memory: &mut [Bits], addr: &Bits, value: &Bits, we: LV) {
+    let targets = candidates(addr);
+    let certain = targets.len() == 1 && we == LV::H;
+    for a in targets {
+        memory[a] = if certain {
+            value.clone()
+        } else {
+            merge([&memory[a], value].into_iter(), value.len())
+        };
+    }
+}
+
+/// The word to write: `din`, or what is on the bidirectional bus.
+fn write_data(data: &GateUpdateData, din: usize) -> Bits {
+    if bidirectional(data.params) {
+        data.inout_values[0].0.clone()
+    } else {
+        data.in_values[din].clone()
+    }
+}
+
+fn memory_init(clocked: bool) -> crate::PinInitFunc {
+    Box::new(move |gpio, params, cm| {
+        if clocked {
+            gpio.add_in(1, cm);
+        }
+        gpio.add_in(addr_bits(params), cm);
+        if !bidirectional(params) {
+            gpio.add_in(width(params), cm);
+        }
+        gpio.add_in(1, cm);
+        gpio.add_in(1, cm);
+        if bidirectional(params) {
+            gpio.add_in_out(width(params), cm);
+        } else {
+            gpio.add_out(width(params), cm);
+        }
+    })
+}
+
+pub(crate) fn register(constructors: &mut HashMap<&'static str, GateConstructor>) {
+    let rom = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            gpio.add_in(addr_bits(params), cm);
+            gpio.add_out(width(params), cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let memory = take_memory(data.params, data.state);
+            let word = read(&memory, &data.in_values[0], width(data.params));
+            data.state.set("memory", Param::Words(memory));
+            dispatch_output_update(1, 0, word);
+        })),
+        initial_update: true,
+    };
+    constructors.insert("rom", rom);
+
+    let ram = GateConstructor {
+        init: memory_init(false),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let w = width(data.params);
+            let bidirectional = bidirectional(data.params);
+            let (addr, din) = (0, 1);
+            let (we, en) = if bidirectional { (1, 2) } else { (2, 3) };
+            let en = data.in_values[en].get(0);
+            let we = data.in_values[we].get(0);
+            let addr = data.in_values[addr].clone();
+
+            let mut memory = take_memory(data.params, data.state);
+            let write_enable = en.and(&we);
+            if write_enable != LV::L {
+                write(&mut memory, &addr, &write_data(data, din), write_enable);
+            }
+            let word = read(&memory, &addr, w);
+            data.state.set("memory", Param::Words(memory));
+
+            let value = if bidirectional {
+                choose(en.and(&we.not()), word, Bits::new(w))
+            } else {
+                choose(en, word, Bits::new(w))
+            };
+            dispatch_output_update(1, 0, value);
+        })),
+        initial_update: false,
+    };
+    constructors.insert("ram", ram);
+
+    let sync_ram = GateConstructor {
+        init: memory_init(true),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let w = width(data.params);
+            let bidirectional = bidirectional(data.params);
+            let (addr, din) = (1, 2);
+            let (we, en) = if bidirectional { (2, 3) } else { (3, 4) };
+            let en = data.in_values[en].get(0);
+            let we = data.in_values[we].get(0);
+            let addr = data.in_values[addr].clone();
+
+            let edge = clock_edge(data);
+            let latched = data.state.get_bits("out", w).unwrap_or_else(|| unknown(w));
+            let reading = match data.state.get("reading") {
+                Some(Param::Bits(b)) => b.get(0),
+                _ => LV::L,
+            };
+            let (latched, reading) = if edge != LV::L {
+                let mut memory = take_memory(data.params, data.state);
+                let enabled = edge.and(&en);
+                let write_enable = enabled.and(&we);
+                if write_enable != LV::L {
+                    write(&mut memory, &addr, &write_data(data, din), write_enable);
+                }
+                let word = read(&memory, &addr, w);
+                data.state.set("memory", Param::Words(memory));
+                (
+                    choose(enabled.clone(), word, latched),
+                    choose(
+                        enabled.clone(),
+                        [we.not()].into_iter().collect(),
+                        [reading].into_iter().collect(),
+                    )
+                    .get(0),
+                )
+            } else {
+                (latched, reading)
+            };
+            data.state.set("out", Param::Bits(latched.clone()));
+            data.state.set(
+                "reading",
+                Param::Bits([reading.clone()].into_iter().collect()),
+            );
+
+            let value = if bidirectional {
+                choose(reading, latched, Bits::new(w))
+            } else {
+                latched
+            };
+            dispatch_output_update(1, 0, value);
+        })),
+        initial_update: false,
+    };
+    constructors.insert("sync_ram", sync_ram);
+}
+
+impl ComponentManager {
+    /// Runs `f` on the contents of a memory component and, if it `writes`,
+    /// refreshes its outputs.
+    fn with_memory<T>(
+        &self,
+        gate_id: &usize,
+        writes: bool,
+        f: impl FnOnce(&mut Vec<Bits>, usize) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let gates = self.gates.read().unwrap();
+        let gate = gates
+            .get(gate_id)
+            .ok_or(format!("The gate with id {} doesn't exist", gate_id))?;
+        if !MEMORIES.contains(&gate.kind.as_str()) {
+            return Err(format!(
+                "Gate {} is a {} but should be one of {:?}",
+                gate_id, gate.kind, MEMORIES
+            ));
+        }
+        let mut state = gate.state.write().unwrap();
+        let mut memory = take_memory(&gate.params, &mut state);
+        let result = f(&mut memory, width(&gate.params));
+        state.set("memory", Param::Words(memory));
+        drop(state);
+        drop(gates);
+        if writes {
+            self.schedule_gate_wakeup(0, *gate_id);
+        }
+        result
+    }
+
+    /// The word at `address` of a `rom`, `ram` or `sync_ram`.
+    pub fn read_memory(&self, gate_id: &usize, address: usize) -> Result<Bits, String> {
+        self.with_memory(gate_id, false, |memory, _| {
+            memory
+                .get(address)
+                .cloned()
+                .ok_or(format!("Address {} is out of range", address))
+        })
+    }
+
+    /// Overwrites the word at `address`. A `sync_ram` shows the change at its
+    /// output only after its next read.
+    pub fn write_memory(&self, gate_id: &usize, address: usize, value: Bits) -> Result<(), String> {
+        self.with_memory(gate_id, true, |memory, width| {
+            if value.len() != width {
+                return Err(format!(
+                    "Gate {} is {} bits wide but got {} bits",
+                    gate_id,
+                    width,
+                    value.len()
+                ));
+            }
+            let word = memory
+                .get_mut(address)
+                .ok_or(format!("Address {} is out of range", address))?;
+            *word = value;
+            Ok(())
+        })
+    }
+
+    /// Overwrites the contents starting at address 0, e.g. with an image
+    /// from `memfile`. Longer images are an error.
+    pub fn load_memory(&self, gate_id: &usize, words: &[Bits]) -> Result<(), String> {
+        self.with_memory(gate_id, true, |memory, width| {
+            if words.len() > memory.len() {
+                return Err(format!(
+                    "The image has {} words but the memory only {}",
+                    words.len(),
+                    memory.len()
+                ));
+            }
+            if let Some(word) = words.iter().find(|w| w.len() != width) {
+                return Err(format!(
+                    "Gate {} is {} bits wide but got {} bits",
+                    gate_id,
+                    width,
+                    word.len()
+                ));
+            }
+            memory[..words.len()].clone_from_slice(words);
+            Ok(())
+        })
+    }
+
+    /// All words of a memory component.
+    pub fn dump_memory(&self, gate_id: &usize) -> Result<Vec<Bits>, String> {
+        self.with_memory(gate_id, false, |memory, _| Ok(memory.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bits::Bits,
+        components::test_util::{bits, eval, show},
+        memfile,
+        params::{Param, Params},
+        run::RunBudget,
+        ComponentManager, PinType,
+    };
+
+    fn params(addr_bits: i64, width: i64) -> Params {
+        Params::new()
+            .with("addr_bits", Param::Int(addr_bits))
+            .with("width", Param::Int(width))
+    }
+
+    #[test]
+    fn test_rom() {
+        let mut cm = ComponentManager::new();
+        let rom = cm
+            .create_gate_with_params(
+                "rom",
+                params(2, 4).with("contents", Param::Text("3 a f".to_string())),
+            )
+            .unwrap();
+        assert_eq!(eval(&mut cm, rom, &["01"]), ["1010"]);
+        assert_eq!(eval(&mut cm, rom, &["11"]), ["XXXX"]);
+        assert_eq!(eval(&mut cm, rom, &["X0"]), ["XX11"]);
+        assert_eq!(show(&cm.read_memory(&rom, 1).unwrap()), "1010");
+        assert_eq!(cm.dump_memory(&rom).unwrap().len(), 4);
+        assert!(!cm.has_pending_events());
+
+        let image = memfile::from_intel_hex(":02000300050AEC\n:00000001FF\n", 4, 16).unwrap();
+        assert!(cm.load_memory(&rom, &image).is_err());
+        cm.load_memory(&rom, &image[..2]).unwrap();
+        cm.write_memory(&rom, 3, bits("0110")).unwrap();
+        assert_eq!(eval(&mut cm, rom, &["11"]), ["0110"]);
+        assert_eq!(show(&cm.read_memory(&rom, 0).unwrap()), "XXXX");
+        assert!(cm.read_memory(&rom, 4).is_err());
+    }
+
+    #[test]
+    fn test_async_ram() {
+        let mut cm = ComponentManager::new();
+        let ram = cm.create_gate_with_params("ram", params(2, 4)).unwrap();
+        // addr, din, we, en
+        assert_eq!(eval(&mut cm, ram, &["10", "0101", "0", "1"]), ["XXXX"]);
+        assert_eq!(eval(&mut cm, ram, &["10", "0101", "1", "1"]), ["0101"]);
+        // Inputs change one after the other, so drop `we` before moving on
+        assert_eq!(eval(&mut cm, ram, &["10", "0101", "0", "1"]), ["0101"]);
+        assert_eq!(eval(&mut cm, ram, &["01", "0101", "0", "1"]), ["XXXX"]);
+        assert_eq!(eval(&mut cm, ram, &["10", "0101", "0", "0"]), ["ZZZZ"]);
+        assert_eq!(eval(&mut cm, ram, &["10", "1100", "X", "1"]), ["X10X"]);
+        let dump = cm.dump_memory(&ram).unwrap();
+        assert_eq!(dump.iter().map(show).collect::<Vec<_>>()[2], "X10X");
+    }
+
+    #[test]
+    fn test_sync_ram() {
+        let mut cm = ComponentManager::new();
+        let ram = cm
+            .create_gate_with_params("sync_ram", params(1, 2))
+            .unwrap();
+        let tick = |cm: &mut ComponentManager, inputs: [&str; 4]| {
+            eval(cm, ram, &[&["0"], &inputs[..]].concat());
+            eval(cm, ram, &[&["1"], &inputs[..]].concat())
+        };
+        // addr, din, we, en
+        assert_eq!(tick(&mut cm, ["1", "10", "1", "1"]), ["10"]);
+        assert_eq!(tick(&mut cm, ["0", "01", "1", "0"]), ["10"]);
+        assert_eq!(show(&cm.read_memory(&ram, 0).unwrap()), "XX");
+        assert_eq!(tick(&mut cm, ["0", "01", "1", "1"]), ["01"]);
+        assert_eq!(tick(&mut cm, ["1", "00", "0", "1"]), ["10"]);
+    }
+
+    #[test]
+    fn test_bidirectional_bus() {
+        let mut cm = ComponentManager::new();
+        let ram = cm
+            .create_gate_with_params("ram", params(1, 2).with("bidirectional", Param::Bool(true)))
+            .unwrap();
+        let driver = cm
+            .create_gate_with_params("input", Params::new().with("width", Param::Int(2)))
+            .unwrap();
+        let bus = cm.create_lump(2);
+        cm.connect_gate_pin_to_lump(&ram, &0, &PinType::INOUT, &bus)
+            .unwrap();
+        cm.connect_gate_pin_to_lump(&driver, &0, &PinType::OUT, &bus)
+            .unwrap();
+        let settle = |cm: &mut ComponentManager| {
+            cm.run_budgeted(&RunBudget::default());
+        };
+
+        // The driver writes 10 to address 1, the RAM keeps off the bus
+        cm.set_input(&driver, bits("10")).unwrap();
+        eval(&mut cm, ram, &["1", "1", "1"]);
+        assert_eq!(show(&cm.get_lump_value(&bus)), "10");
+        assert_eq!(show(&cm.read_memory(&ram, 1).unwrap()), "10");
+
+        // Reading while the driver floats puts the word on the bus
+        cm.set_input(&driver, Bits::new(2)).unwrap();
+        eval(&mut cm, ram, &["1", "0", "1"]);
+        settle(&mut cm);
+        assert_eq!(show(&cm.get_lump_value(&bus)), "10");
+
+        // Both driving different values fight
+        cm.set_input(&driver, bits("01")).unwrap();
+        settle(&mut cm);
+        assert_eq!(show(&cm.get_lump_value(&bus)), "XX");
+    }
+}
@@ -0,0 +1,81 @@
+//! Components of the standard library beyond the basic gates, grouped by
+//! family.
+
+use std::collections::HashMap;
+
+use crate::{
+    params::{Param, Params},
+    GateConstructor,
+};
+
+mod arithmetic;
+mod bus;
+mod clock;
+pub(crate) mod custom;
+mod io;
+mod memory;
+mod routing;
+mod sequential;
+mod shifter;
+mod wiring;
+
+pub(crate) fn register_all(constructors: &mut HashMap<&'static str, GateConstructor>) {
+    arithmetic::register(constructors);
+    bus::register(constructors);
+    clock::register(constructors);
+    io::register(constructors);
+    memory::register(constructors);
+    routing::register(constructors);
+    sequential::register(constructors);
+    shifter::register(constructors);
+    wiring::register(constructors);
+}
+
+/// Bit width of a component given by its `width` parameter, at least 1.
+pub(crate) fn width(params: &Params) -> usize {
+    params.get_int("width", 1).max(1) as usize
+}
+
+/// A text parameter such as a mode name, `default` if it is missing.
+pub(crate) fn text<'a>(params: &'a Params, name: &str, default: &'a str) -> &'a str {
+    match params.get(name) {
+        Some(Param::Text(t)) => t,
+        _ => default,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use crate::{
+        bits::{Bits, LV},
+        run::RunBudget,
+        ComponentManager, PinType,
+    };
+
+    /// Parses bits written most significant bit first, like a number.
+    pub(crate) fn bits(s: &str) -> Bits {
+        s.chars().rev().map(|c| LV::from_char(c).unwrap()).collect()
+    }
+
+    pub(crate) fn show(value: &Bits) -> String {
+        (0..value.len())
+            .rev()
+            .map(|i| value.get(i).to_string())
+            .collect()
+    }
+
+    /// Drives the inputs of `gate`, runs until nothing changes anymore and
+    /// returns all outputs, both written most significant bit first.
+    pub(crate) fn eval(cm: &mut ComponentManager, gate: usize, inputs: &[&str]) -> Vec<String> {
+        let pins = cm.try_get_gate_pins(&gate, &PinType::IN).unwrap();
+        for (pin, value) in pins.iter().zip(inputs) {
+            cm.schedule_pin_update(0, *pin, bits(value));
+        }
+        cm.run_budgeted(&RunBudget::default());
+        cm.try_get_gate_pins(&gate, &PinType::OUT)
+            .unwrap()
+            .iter()
+            .map(|p| show(&cm.get_pin_value(p)))
+            .collect()
+    }
+}
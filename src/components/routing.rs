@@ -0,0 +1,268 @@
+//! Selection and routing components.
+//!
+//! All of them take `select_bits` (n, default 1) and, where data is routed,
+//! `width` (default 1):
+//! - `mux`: inputs `d0..d(2^n-1)` and `sel`, output `y`
+//! - `demux`: inputs `d` and `sel`, outputs `y0..y(2^n-1)`, unselected
+//!   outputs are 0
+//! - `decoder`: inputs `sel` and `en`, one bit outputs `y0..y(2^n-1)`
+//! - `encoder`: one bit inputs `d0..d(2^n-1)`, output the n bit index of the
+//!   high input
+//! - `priority_encoder`: like `encoder` but the highest active input wins,
+//!   with an additional `valid` output
+//!
+//! Unknown select bits make every input they could address a candidate.
+//! Where the candidates agree the output is still known, otherwise it is X.
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    bits::{Bits, LV},
+    params::Params,
+    GateConstructor,
+};
+
+use super::width;
+
+fn select_bits(params: &Params) -> usize {
+    params.get_int("select_bits", 1).clamp(1, 16) as usize
+}
+
+/// Indices `sel` can address, treating X and Z bits as both 0 and 1.
+pub(super) fn candidates(sel: &Bits) -> Vec<usize> {
+    let mut found = vec![0];
+    for i in 0..sel.len() {
+        match sel.get(i) {
+            LV::H => found.iter_mut().for_each(|c| *c |= 1 << i),
+            LV::L => {}
+            _ => {
+                let with_bit: Vec<usize> = found.iter().map(|c| c | 1 << i).collect();
+                found.extend(with_bit);
+            }
+        }
+    }
+    found
+}
+
+/// Bitwise agreement of all `values`: a bit stays known only if every value
+/// has the same known level there.
+pub(super) fn merge<'a>(mut values: impl Iterator<Item = &'a Bits>, width: usize) -> Bits {
+    let Some(first) = values.next() else {
+        return (0..width).map(|_| LV::X).collect();
+    };
+    let mut merged: Vec<LV> = (0..width)
+        .map(|i| match first.get(i) {
+            LV::Z => LV::X,
+            lv => lv,
+        })
+        .collect();
+    for value in values {
+        for (i, lv) in merged.iter_mut().enumerate() {
+            if *lv != value.get(i) {
+                *lv = LV::X;
+            }
+        }
+    }
+    merged.into_iter().collect()
+}
+
+fn index_bits(index: usize, n: usize) -> Bits {
+    Bits::new(n).set_num(index as i64)
+}
+
+pub(crate) fn register(constructors: &mut HashMap<&'static str, GateConstructor>) {
+    let mux = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            for _ in 0..1 << select_bits(params) {
+                gpio.add_in(width(params), cm);
+            }
+            gpio.add_in(select_bits(params), cm);
+            gpio.add_out(width(params), cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let inputs = 1 << select_bits(data.params);
+            let sel = &data.in_values[inputs];
+            let chosen = candidates(sel).into_iter().map(|i| &data.in_values[i]);
+            dispatch_output_update(1, 0, merge(chosen, width(data.params)));
+        })),
+        initial_update: false,
+    };
+    constructors.insert("mux", mux);
+
+    let demux = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            gpio.add_in(width(params), cm);
+            gpio.add_in(select_bits(params), cm);
+            for _ in 0..1 << select_bits(params) {
+                gpio.add_out(width(params), cm);
+            }
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let w = width(data.params);
+            let d = &data.in_values[0];
+            let chosen = candidates(&data.in_values[1]);
+            let zero = Bits::new(w).set_num(0);
+            for out in 0..1 << select_bits(data.params) {
+                let value = if !chosen.contains(&out) {
+                    zero.clone()
+                } else if chosen.len() == 1 {
+                    merge([d].into_iter(), w)
+                } else {
+                    merge([d, &zero].into_iter(), w)
+                };
+                dispatch_output_update(1, out, value);
+            }
+        })),
+        initial_update: false,
+    };
+    constructors.insert("demux", demux);
+
+    let decoder = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            gpio.add_in(select_bits(params), cm);
+            gpio.add_in(1, cm);
+            for _ in 0..1 << select_bits(params) {
+                gpio.add_out(1, cm);
+            }
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let chosen = candidates(&data.in_values[0]);
+            let enable = data.in_values[1].get(0);
+            for out in 0..1 << select_bits(data.params) {
+                let lv = if !chosen.contains(&out) || enable == LV::L {
+                    LV::L
+                } else if chosen.len() == 1 && enable == LV::H {
+                    LV::H
+                } else {
+                    LV::X
+                };
+                dispatch_output_update(1, out, [lv].into_iter().collect());
+            }
+        })),
+        initial_update: false,
+    };
+    constructors.insert("decoder", decoder);
+
+    let encoder = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            for _ in 0..1 << select_bits(params) {
+                gpio.add_in(1, cm);
+            }
+            gpio.add_out(select_bits(params), cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            // Bit b of the code is the or of all inputs whose index has bit b
+            let n = select_bits(data.params);
+            let code = (0..n)
+                .map(|b| {
+                    data.in_values
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| i & (1 << b) != 0)
+                        .fold(LV::L, |acc, (_, d)| acc.or(&d.get(0)))
+                })
+                .collect();
+            dispatch_output_update(1, 0, code);
+        })),
+        initial_update: false,
+    };
+    constructors.insert("encoder", encoder);
+
+    let priority_encoder = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            for _ in 0..1 << select_bits(params) {
+                gpio.add_in(1, cm);
+            }
+            gpio.add_out(select_bits(params), cm);
+            gpio.add_out(1, cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let n = select_bits(data.params);
+            // Walk down from the highest input, unknown inputs might be the
+            // winner but don't stop the search
+            let mut winners = Vec::new();
+            let mut found_high = false;
+            for i in (0..data.in_values.len()).rev() {
+                match data.in_values[i].get(0) {
+                    LV::L => {}
+                    LV::H => {
+                        winners.push(index_bits(i, n));
+                        found_high = true;
+                        break;
+                    }
+                    _ => winners.push(index_bits(i, n)),
+                }
+            }
+            let valid = if found_high {
+                LV::H
+            } else if winners.is_empty() {
+                LV::L
+            } else {
+                LV::X
+            };
+            if !found_high {
+                winners.push(index_bits(0, n));
+            }
+            dispatch_output_update(1, 0, merge(winners.iter(), n));
+            dispatch_output_update(1, 1, [valid].into_iter().collect());
+        })),
+        initial_update: false,
+    };
+    constructors.insert("priority_encoder", priority_encoder);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::test_util::eval,
+        params::{Param, Params},
+        ComponentManager,
+    };
+
+    fn params(select_bits: i64, width: i64) -> Params {
+        Params::new()
+            .with("select_bits", Param::Int(select_bits))
+            .with("width", Param::Int(width))
+    }
+
+    #[test]
+    fn test_mux() {
+        let mut cm = ComponentManager::new();
+        let mux = cm.create_gate_with_params("mux", params(2, 2)).unwrap();
+        assert_eq!(eval(&mut cm, mux, &["00", "01", "10", "11", "10"]), ["10"]);
+        assert_eq!(eval(&mut cm, mux, &["00", "01", "10", "11", "0X"]), ["0X"]);
+        assert_eq!(eval(&mut cm, mux, &["01", "01", "11", "11", "X0"]), ["X1"]);
+        assert_eq!(eval(&mut cm, mux, &["01", "01", "01", "01", "XZ"]), ["01"]);
+    }
+
+    #[test]
+    fn test_demux_and_decoder() {
+        let mut cm = ComponentManager::new();
+        let demux = cm.create_gate_with_params("demux", params(1, 2)).unwrap();
+        assert_eq!(eval(&mut cm, demux, &["11", "1"]), ["00", "11"]);
+        assert_eq!(eval(&mut cm, demux, &["10", "X"]), ["X0", "X0"]);
+
+        let decoder = cm.create_gate_with_params("decoder", params(2, 1)).unwrap();
+        assert_eq!(eval(&mut cm, decoder, &["10", "1"]), ["0", "0", "1", "0"]);
+        assert_eq!(eval(&mut cm, decoder, &["10", "0"]), ["0", "0", "0", "0"]);
+        assert_eq!(eval(&mut cm, decoder, &["X1", "1"]), ["0", "X", "0", "X"]);
+    }
+
+    #[test]
+    fn test_encoders() {
+        let mut cm = ComponentManager::new();
+        let encoder = cm.create_gate_with_params("encoder", params(2, 1)).unwrap();
+        assert_eq!(eval(&mut cm, encoder, &["0", "0", "1", "0"]), ["10"]);
+        assert_eq!(eval(&mut cm, encoder, &["0", "X", "0", "0"]), ["0X"]);
+
+        let priority = cm
+            .create_gate_with_params("priority_encoder", params(2, 1))
+            .unwrap();
+        assert_eq!(eval(&mut cm, priority, &["1", "1", "0", "1"]), ["11", "1"]);
+        assert_eq!(eval(&mut cm, priority, &["1", "0", "1", "0"]), ["10", "1"]);
+        assert_eq!(eval(&mut cm, priority, &["0", "0", "0", "0"]), ["00", "0"]);
+        assert_eq!(eval(&mut cm, priority, &["0", "1", "X", "0"]), ["XX", "1"]);
+        assert_eq!(eval(&mut cm, priority, &["0", "X", "0", "0"]), ["0X", "X"]);
+        assert_eq!(eval(&mut cm, priority, &["1", "0", "0", "X"]), ["XX", "1"]);
+    }
+}
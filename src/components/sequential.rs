@@ -0,0 +1,302 @@
+//! Clocked storage components.
+//!
+//! All of them start with inputs `clk`, `rst` and `en` (one bit each) and
+//! take these parameters:
+//! - `width`: number of stored bits (default 1)
+//! - `edge`: `"rising"` (default) or `"falling"` clock edge
+//! - `reset_value`: value loaded while `rst` is high (default 0)
+//! - `async_reset`: apply `rst` immediately instead of at the next clock
+//!   edge (default `false`)
+//!
+//! The components and their further pins:
+//! - `register`: input `d`, output `q`; loads `d` when enabled
+//! - `counter`: inputs `up`, `load` and `d`, outputs `q` and `tc`; loads `d`
+//!   or counts up or down when enabled, `tc` is high while the next count
+//!   wraps around
+//! - `sipo_shift_register`: input `sin`, output `q`; shifts `q` towards its
+//!   most significant bit, `sin` enters at bit 0
+//! - `piso_shift_register`: inputs `load` and `d`, output `sout`; loads `d`
+//!   or shifts like `sipo_shift_register` with 0 entering, `sout` is the
+//!   most significant bit
+//!
+//! The stored bits start out unknown. Where an unknown control input or clock
+//! transition leaves open whether a bit changes, it becomes X.
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    bits::{Bits, Shift, LV},
+    params::{Param, Params},
+    ComponentManager, GPIOHandler, GateConstructor, GateUpdateData,
+};
+
+use super::{routing::merge, text, width};
+
+const CLK: usize = 0;
+const RST: usize = 1;
+const EN: usize = 2;
+
+/// `if_high` or `if_low` depending on `select`, their agreement if it is
+/// unknown.
+pub(super) fn choose(select: LV, if_high: Bits, if_low: Bits) -> Bits {
+    match select {
+        LV::H => if_high,
+        LV::L => if_low,
+        _ => {
+            let width = if_high.len();
+            merge([if_high, if_low].iter(), width)
+        }
+    }
+}
+
+/// Whether the clock input made the active transition since the last
+/// update: H for certain, X if an unknown level might hide one.
+pub(super) fn clock_edge(data: &mut GateUpdateData) -> LV {
+    let now = data.in_values[CLK].get(0);
+    let previous = data
+        .state
+        .set("clk", Param::Bits([now.clone()].into_iter().collect()))
+        .and_then(|p| match p {
+            Param::Bits(b) => Some(b.get(0)),
+            _ => None,
+        });
+    let Some(previous) = previous else {
+        return LV::L;
+    };
+    let (from, to) = match text(data.params, "edge", "rising") {
+        "falling" => (LV::H, LV::L),
+        _ => (LV::L, LV::H),
+    };
+    if previous == from && now == to {
+        LV::H
+    } else if previous != now && previous != to && now != from {
+        LV::X
+    } else {
+        LV::L
+    }
+}
+
+/// Advances the stored bits by one update and returns them. `next` computes
+/// the bits to store on an enabled clock edge from the inputs and the
+/// current bits.
+fn step(data: &mut GateUpdateData, next: fn(&[Bits], &Bits) -> Bits) -> Bits {
+    let width = width(data.params);
+    let q = data
+        .state
+        .get_bits("q", width)
+        .unwrap_or_else(|| (0..width).map(|_| LV::X).collect());
+    let reset_value = data
+        .params
+        .get_bits("reset_value", width)
+        .unwrap_or_else(|| Bits::new(width).set_num(0));
+    let async_reset = data.params.get_bool("async_reset", false);
+    let rst = data.in_values[RST].get(0);
+
+    let mut clocked = choose(
+        data.in_values[EN].get(0),
+        next(&data.in_values, &q),
+        q.clone(),
+    );
+    if !async_reset {
+        clocked = choose(rst.clone(), reset_value.clone(), clocked);
+    }
+    let mut q = choose(clock_edge(data), clocked, q);
+    if async_reset {
+        q = choose(rst, reset_value, q);
+    }
+    data.state.set("q", Param::Bits(q.clone()));
+    q
+}
+
+/// Pin setup with `clk`, `rst` and `en` followed by inputs and outputs of
+/// the given widths.
+fn stateful(
+    extra_in: fn(&Params) -> Vec<usize>,
+    out: fn(&Params) -> Vec<usize>,
+) -> impl Fn(&mut GPIOHandler, &Params, &ComponentManager) {
+    move |gpio, params, cm| {
+        for _ in [CLK, RST, EN] {
+            gpio.add_in(1, cm);
+        }
+        for w in extra_in(params) {
+            gpio.add_in(w, cm);
+        }
+        for w in out(params) {
+            gpio.add_out(w, cm);
+        }
+    }
+}
+
+/// `q` shifted towards its most significant bit with `lv` entering.
+fn shift_in(q: &Bits, lv: LV) -> Bits {
+    let mut shifted = q.shift(Shift::Left, 1);
+    if !shifted.is_empty() {
+        shifted.set(0, lv);
+    }
+    shifted
+}
+
+pub(crate) fn register(constructors: &mut HashMap<&'static str, GateConstructor>) {
+    let register = GateConstructor {
+        init: Box::new(stateful(|p| vec![width(p)], |p| vec![width(p)])),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let q = step(data, |inputs, _| inputs[3].clone());
+            dispatch_output_update(1, 0, q);
+        })),
+        initial_update: false,
+    };
+    constructors.insert("register", register);
+
+    let counter = GateConstructor {
+        init: Box::new(stateful(|p| vec![1, 1, width(p)], |p| vec![width(p), 1])),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let q = step(data, |inputs, q| {
+                let one = Bits::new(q.len()).set_num(1);
+                let counted = choose(
+                    inputs[3].get(0),
+                    q.add(&one, &LV::L).0,
+                    q.sub(&one, &LV::L).0,
+                );
+                choose(inputs[4].get(0), inputs[5].clone(), counted)
+            });
+            let all = |lv: LV| -> Bits { (0..q.len()).map(|_| lv.clone()).collect() };
+            let tc = choose(
+                data.in_values[3].get(0),
+                [q.eq_lv(&all(LV::H))].into_iter().collect(),
+                [q.eq_lv(&all(LV::L))].into_iter().collect(),
+            );
+            dispatch_output_update(1, 0, q);
+            dispatch_output_update(1, 1, tc);
+        })),
+        initial_update: false,
+    };
+    constructors.insert("counter", counter);
+
+    let sipo = GateConstructor {
+        init: Box::new(stateful(|_| vec![1], |p| vec![width(p)])),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let q = step(data, |inputs, q| shift_in(q, inputs[3].get(0)));
+            dispatch_output_update(1, 0, q);
+        })),
+        initial_update: false,
+    };
+    constructors.insert("sipo_shift_register", sipo);
+
+    let piso = GateConstructor {
+        init: Box::new(stateful(|p| vec![1, width(p)], |_| vec![1])),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let q = step(data, |inputs, q| {
+                choose(inputs[3].get(0), inputs[4].clone(), shift_in(q, LV::L))
+            });
+            let msb = q.get(q.len() - 1);
+            dispatch_output_update(1, 0, [msb].into_iter().collect());
+        })),
+        initial_update: false,
+    };
+    constructors.insert("piso_shift_register", piso);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::test_util::eval,
+        params::{Param, Params},
+        ComponentManager,
+    };
+
+    fn params(width: i64) -> Params {
+        Params::new().with("width", Param::Int(width))
+    }
+
+    /// Evaluates with `clk` low and then high, returning the outputs after
+    /// the rising edge.
+    fn tick(cm: &mut ComponentManager, gate: usize, inputs: &[&str]) -> Vec<String> {
+        eval(cm, gate, &[&["0"], inputs].concat());
+        eval(cm, gate, &[&["1"], inputs].concat())
+    }
+
+    #[test]
+    fn test_register() {
+        let mut cm = ComponentManager::new();
+        let reg = cm.create_gate_with_params("register", params(4)).unwrap();
+        assert_eq!(eval(&mut cm, reg, &["0", "0", "1", "1010"]), ["XXXX"]);
+        assert_eq!(tick(&mut cm, reg, &["0", "1", "1010"]), ["1010"]);
+        assert_eq!(tick(&mut cm, reg, &["0", "0", "0110"]), ["1010"]);
+        assert_eq!(tick(&mut cm, reg, &["0", "X", "0110"]), ["XX10"]);
+        // Synchronous reset waits for the edge
+        assert_eq!(eval(&mut cm, reg, &["0", "1", "1", "0110"]), ["XX10"]);
+        assert_eq!(eval(&mut cm, reg, &["1", "1", "1", "0110"]), ["0000"]);
+    }
+
+    #[test]
+    fn test_falling_edge_and_async_reset() {
+        let mut cm = ComponentManager::new();
+        let reg = cm
+            .create_gate_with_params(
+                "register",
+                params(2)
+                    .with("edge", Param::Text("falling".to_string()))
+                    .with("async_reset", Param::Bool(true))
+                    .with("reset_value", Param::Int(2)),
+            )
+            .unwrap();
+        assert_eq!(eval(&mut cm, reg, &["1", "0", "1", "01"]), ["XX"]);
+        assert_eq!(eval(&mut cm, reg, &["0", "0", "1", "01"]), ["01"]);
+        assert_eq!(eval(&mut cm, reg, &["0", "1", "1", "01"]), ["10"]);
+    }
+
+    #[test]
+    fn test_counter() {
+        let mut cm = ComponentManager::new();
+        let counter = cm.create_gate_with_params("counter", params(2)).unwrap();
+        // rst, en, up, load, d
+        assert_eq!(
+            tick(&mut cm, counter, &["1", "1", "1", "0", "00"]),
+            ["00", "0"]
+        );
+        assert_eq!(
+            tick(&mut cm, counter, &["0", "1", "1", "0", "00"]),
+            ["01", "0"]
+        );
+        assert_eq!(
+            tick(&mut cm, counter, &["0", "1", "1", "1", "11"]),
+            ["11", "1"]
+        );
+        assert_eq!(
+            tick(&mut cm, counter, &["0", "1", "1", "0", "00"]),
+            ["00", "0"]
+        );
+        assert_eq!(
+            tick(&mut cm, counter, &["0", "1", "0", "0", "00"]),
+            ["11", "0"]
+        );
+        assert_eq!(
+            tick(&mut cm, counter, &["0", "0", "0", "0", "00"]),
+            ["11", "0"]
+        );
+        assert_eq!(
+            eval(&mut cm, counter, &["1", "0", "1", "1", "0", "00"]),
+            ["11", "1"]
+        );
+    }
+
+    #[test]
+    fn test_shift_registers() {
+        let mut cm = ComponentManager::new();
+        let sipo = cm
+            .create_gate_with_params("sipo_shift_register", params(3))
+            .unwrap();
+        tick(&mut cm, sipo, &["1", "1", "0"]);
+        assert_eq!(tick(&mut cm, sipo, &["0", "1", "1"]), ["001"]);
+        assert_eq!(tick(&mut cm, sipo, &["0", "1", "0"]), ["010"]);
+        assert_eq!(tick(&mut cm, sipo, &["0", "1", "1"]), ["101"]);
+
+        let piso = cm
+            .create_gate_with_params("piso_shift_register", params(3))
+            .unwrap();
+        assert_eq!(tick(&mut cm, piso, &["0", "1", "1", "011"]), ["0"]);
+        assert_eq!(tick(&mut cm, piso, &["0", "1", "0", "000"]), ["1"]);
+        assert_eq!(tick(&mut cm, piso, &["0", "1", "0", "000"]), ["1"]);
+        assert_eq!(tick(&mut cm, piso, &["0", "1", "0", "000"]), ["0"]);
+    }
+}
@@ -0,0 +1,119 @@
+//! Barrel shifter.
+//!
+//! Inputs `d` (`width` bits, default 1) and `amount` (`amount_bits`, by
+//! default just enough to count up to `width - 1`), output `y`.
+//!
+//! Parameters:
+//! - `mode`: `"logical"` (default), `"arithmetic"` or `"rotate"`; arithmetic
+//!   shifts repeat the sign bit when shifting right and fill with 0 when
+//!   shifting left
+//! - `direction`: `"left"` (default), `"right"`, or `"input"` for an
+//!   additional one bit input `right` that selects the direction at run time
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    bits::{Bits, Shift, LV},
+    params::Params,
+    GateConstructor,
+};
+
+use super::{text, width};
+
+fn amount_bits(params: &Params) -> usize {
+    let default = (usize::BITS - (width(params) - 1).leading_zeros()).max(1);
+    params.get_int("amount_bits", default as i64).clamp(1, 64) as usize
+}
+
+fn shift_kind(params: &Params, right: bool) -> Shift {
+    match (text(params, "mode", "logical"), right) {
+        ("rotate", false) => Shift::RotateLeft,
+        ("rotate", true) => Shift::RotateRight,
+        ("arithmetic", true) => Shift::ArithmeticRight,
+        (_, true) => Shift::Right,
+        (_, false) => Shift::Left,
+    }
+}
+
+pub(crate) fn register(constructors: &mut HashMap<&'static str, GateConstructor>) {
+    let barrel_shifter = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            gpio.add_in(width(params), cm);
+            gpio.add_in(amount_bits(params), cm);
+            if text(params, "direction", "left") == "input" {
+                gpio.add_in(1, cm);
+            }
+            gpio.add_out(width(params), cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let d = &data.in_values[0];
+            let amount = &data.in_values[1];
+            let shifted = |right| d.shift_by(shift_kind(data.params, right), amount);
+            let y = match text(data.params, "direction", "left") {
+                "right" => shifted(true),
+                "input" => match data.in_values[2].get(0) {
+                    LV::H => shifted(true),
+                    LV::L => shifted(false),
+                    _ => {
+                        let (left, right) = (shifted(false), shifted(true));
+                        (0..d.len())
+                            .map(|i| match (left.get(i), right.get(i)) {
+                                (a, b) if a == b => a,
+                                _ => LV::X,
+                            })
+                            .collect::<Bits>()
+                    }
+                },
+                _ => shifted(false),
+            };
+            dispatch_output_update(1, 0, y);
+        })),
+        initial_update: false,
+    };
+    constructors.insert("barrel_shifter", barrel_shifter);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::test_util::eval,
+        params::{Param, Params},
+        ComponentManager,
+    };
+
+    fn params(mode: &str, direction: &str) -> Params {
+        Params::new()
+            .with("width", Param::Int(4))
+            .with("mode", Param::Text(mode.to_string()))
+            .with("direction", Param::Text(direction.to_string()))
+    }
+
+    #[test]
+    fn test_fixed_direction() {
+        let mut cm = ComponentManager::new();
+        let shl = cm
+            .create_gate_with_params("barrel_shifter", params("logical", "left"))
+            .unwrap();
+        let sar = cm
+            .create_gate_with_params("barrel_shifter", params("arithmetic", "right"))
+            .unwrap();
+        let ror = cm
+            .create_gate_with_params("barrel_shifter", params("rotate", "right"))
+            .unwrap();
+        assert_eq!(eval(&mut cm, shl, &["1011", "01"]), ["0110"]);
+        assert_eq!(eval(&mut cm, sar, &["1011", "10"]), ["1110"]);
+        assert_eq!(eval(&mut cm, ror, &["1011", "11"]), ["0111"]);
+        assert_eq!(eval(&mut cm, shl, &["0011", "0X"]), ["0X1X"]);
+    }
+
+    #[test]
+    fn test_direction_input() {
+        let mut cm = ComponentManager::new();
+        let shifter = cm
+            .create_gate_with_params("barrel_shifter", params("logical", "input"))
+            .unwrap();
+        assert_eq!(eval(&mut cm, shifter, &["0110", "01", "0"]), ["1100"]);
+        assert_eq!(eval(&mut cm, shifter, &["0110", "01", "1"]), ["0011"]);
+        assert_eq!(eval(&mut cm, shifter, &["0100", "01", "X"]), ["X0X0"]);
+    }
+}
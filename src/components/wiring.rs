@@ -0,0 +1,229 @@
+//! Components that only rearrange the bits of buses, with no delay.
+//!
+//! - `splitter`: input `width` bits (default 1), one output per group of
+//!   `map`
+//! - `merger`: one input per group of `map`, output `width` bits; bits no
+//!   group mentions are driven Z and bits driven differently by two groups X
+//! - `bus_slice`: input `width` bits, output the `length` bits (default 1)
+//!   starting at bit `start` (default 0)
+//!
+//! `map` is a text parameter listing for each group the bus bits it consists
+//! of, least significant first. Groups are separated by `,`, bits within a
+//! group by spaces, and `a-b` stands for all bits from `a` to `b`, counting
+//! down if `b` is smaller. `"0-3, 4-7"` splits a byte into its nibbles and
+//! `"7-0"` reverses it. Without a `map` every bit is its own group, and so
+//! it is with an invalid one, which is logged as a warning.
+
+use std::{collections::HashMap, sync::Arc};
+
+use tracing::warn;
+
+use crate::{
+    bits::{Bits, LV},
+    params::{Param, Params},
+    GateConstructor,
+};
+
+use super::width;
+
+/// Parses one group, `None` if it is empty or mentions bits outside the bus.
+fn parse_group(group: &str, width: usize) -> Option<Vec<usize>> {
+    let mut bits = Vec::new();
+    for item in group.split_whitespace() {
+        let (from, to): (usize, usize) = match item.split_once('-') {
+            Some((from, to)) => (from.parse().ok()?, to.parse().ok()?),
+            None => {
+                let bit = item.parse().ok()?;
+                (bit, bit)
+            }
+        };
+        if from >= width || to >= width {
+            return None;
+        }
+        if from <= to {
+            bits.extend(from..=to);
+        } else {
+            bits.extend((to..=from).rev());
+        }
+    }
+    (!bits.is_empty()).then_some(bits)
+}
+
+/// The groups of `map`, one group per bit without a `map`.
+fn parse_groups(params: &Params) -> Result<Vec<Vec<usize>>, String> {
+    let width = width(params);
+    match params.get("map") {
+        Some(Param::Text(map)) => map
+            .split(',')
+            .map(|group| parse_group(group, width))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(format!("Invalid map '{}' for a {} bit bus", map, width)),
+        _ => Ok((0..width).map(|bit| vec![bit]).collect()),
+    }
+}
+
+/// The groups of `map`, falling back to one group per bit.
+fn groups(params: &Params) -> Vec<Vec<usize>> {
+    parse_groups(params).unwrap_or_else(|_| (0..width(params)).map(|bit| vec![bit]).collect())
+}
+
+/// Like `groups`, but tells about an invalid `map` while the pins are made.
+fn init_groups(params: &Params) -> Vec<Vec<usize>> {
+    if let Err(e) = parse_groups(params) {
+        warn!("{}, using one group per bit", e);
+    }
+    groups(params)
+}
+
+/// Collects `bits` of `bus`, slicing out contiguous runs at once.
+fn gather(bus: &Bits, bits: &[usize]) -> Bits {
+    let mut result = Bits::new(0);
+    let mut start = 0;
+    while start < bits.len() {
+        let mut end = start + 1;
+        while end < bits.len() && bits[end] == bits[end - 1] + 1 {
+            end += 1;
+        }
+        result = result.concat(&bus.slice(bits[start], bits[end - 1] + 1));
+        start = end;
+    }
+    result
+}
+
+fn slice_range(params: &Params) -> (usize, usize) {
+    let width = width(params);
+    let start = (params.get_int("start", 0).max(0) as usize).min(width - 1);
+    let length = (params.get_int("length", 1).max(1) as usize).min(width - start);
+    (start, start + length)
+}
+
+pub(crate) fn register(constructors: &mut HashMap<&'static str, GateConstructor>) {
+    let splitter = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            gpio.add_in(width(params), cm);
+            for group in init_groups(params) {
+                gpio.add_out(group.len(), cm);
+            }
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            for (out, group) in groups(data.params).iter().enumerate() {
+                dispatch_output_update(0, out, gather(&data.in_values[0], group));
+            }
+        })),
+        initial_update: false,
+    };
+    constructors.insert("splitter", splitter);
+
+    let merger = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            for group in init_groups(params) {
+                gpio.add_in(group.len(), cm);
+            }
+            gpio.add_out(width(params), cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let mut bus = Bits::new(width(data.params));
+            let mut driven = vec![false; bus.len()];
+            for (group, value) in groups(data.params).iter().zip(data.in_values.iter()) {
+                for (i, bit) in group.iter().enumerate() {
+                    let lv = if driven[*bit] && bus.get(*bit) != value.get(i) {
+                        LV::X
+                    } else {
+                        value.get(i)
+                    };
+                    bus.set(*bit, lv);
+                    driven[*bit] = true;
+                }
+            }
+            dispatch_output_update(0, 0, bus);
+        })),
+        initial_update: false,
+    };
+    constructors.insert("merger", merger);
+
+    let bus_slice = GateConstructor {
+        init: Box::new(|gpio, params, cm| {
+            let (start, end) = slice_range(params);
+            gpio.add_in(width(params), cm);
+            gpio.add_out(end - start, cm);
+        }),
+        update: Arc::new(Box::new(|data, dispatch_output_update| {
+            let (start, end) = slice_range(data.params);
+            dispatch_output_update(0, 0, data.in_values[0].slice(start, end));
+        })),
+        initial_update: false,
+    };
+    constructors.insert("bus_slice", bus_slice);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_groups;
+    use crate::{
+        components::test_util::eval,
+        params::{Param, Params},
+        ComponentManager, PinType,
+    };
+
+    fn params(width: i64, map: &str) -> Params {
+        Params::new()
+            .with("width", Param::Int(width))
+            .with("map", Param::Text(map.to_string()))
+    }
+
+    #[test]
+    fn test_splitter() {
+        let mut cm = ComponentManager::new();
+        let nibbles = cm
+            .create_gate_with_params("splitter", params(8, "0-3, 4-7"))
+            .unwrap();
+        assert_eq!(eval(&mut cm, nibbles, &["1100X010"]), ["X010", "1100"]);
+
+        let scattered = cm
+            .create_gate_with_params("splitter", params(4, "3-0, 1 1"))
+            .unwrap();
+        assert_eq!(eval(&mut cm, scattered, &["1000"]), ["0001", "00"]);
+
+        let bits = cm
+            .create_gate_with_params("splitter", params(3, "0-7"))
+            .unwrap();
+        assert_eq!(eval(&mut cm, bits, &["10Z"]), ["Z", "0", "1"]);
+        assert!(parse_groups(&params(3, "0-7")).is_err());
+        assert!(parse_groups(&params(3, "0, ,1")).is_err());
+        assert_eq!(parse_groups(&params(3, "2-1")).unwrap(), [vec![2, 1]]);
+
+        // A narrower bus can't be connected
+        let lump = cm.create_lump(4);
+        assert!(cm
+            .connect_gate_pin_to_lump(&nibbles, &0, &PinType::IN, &lump)
+            .is_err());
+        assert!(cm
+            .connect_gate_pin_to_lump(&nibbles, &0, &PinType::OUT, &lump)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_merger() {
+        let mut cm = ComponentManager::new();
+        let merger = cm
+            .create_gate_with_params("merger", params(6, "0-1, 4-5, 1"))
+            .unwrap();
+        assert_eq!(eval(&mut cm, merger, &["01", "11", "0"]), ["11ZZ01"]);
+        assert_eq!(eval(&mut cm, merger, &["01", "11", "1"]), ["11ZZX1"]);
+    }
+
+    #[test]
+    fn test_bus_slice() {
+        let mut cm = ComponentManager::new();
+        let slice = cm
+            .create_gate_with_params(
+                "bus_slice",
+                Params::new()
+                    .with("width", Param::Int(8))
+                    .with("start", Param::Int(2))
+                    .with("length", Param::Int(3)),
+            )
+            .unwrap();
+        assert_eq!(eval(&mut cm, slice, &["10110100"]), ["101"]);
+    }
+}
@@ -1,34 +1,67 @@
-use crate::bits::Bits;
+use crate::bits::{Bits, Strength};
 
 #[derive(Debug)]
 pub struct PinUpdateEvent {
     pub time: u64,
+    /// Order of scheduling, events of the same time are handled first in
+    /// first out so the latest update of a pin wins
+    pub seq: u64,
     pub target_pin_id: usize,
     pub value: Bits,
+    /// Whether the gate of the pin drives this value, as opposed to the lump
+    /// passing on what it carries. Only matters for inout pins.
+    pub drive: bool,
 }
 
 impl Ord for PinUpdateEvent {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.time.cmp(&self.time)
+        (other.time, other.seq).cmp(&(self.time, self.seq))
     }
 }
 
 impl PartialOrd for PinUpdateEvent {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        other.time.partial_cmp(&self.time)
+        Some(self.cmp(other))
     }
 }
 
 impl PartialEq for PinUpdateEvent {
     fn eq(&self, other: &Self) -> bool {
-        self.time == other.time
+        (self.time, self.seq) == (other.time, other.seq)
     }
 }
 impl Eq for PinUpdateEvent {}
 
+#[derive(Debug)]
+pub struct GateWakeupEvent {
+    pub time: u64,
+    pub seq: u64,
+    pub target_gate_id: usize,
+}
+
+impl Ord for GateWakeupEvent {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (other.time, other.seq).cmp(&(self.time, self.seq))
+    }
+}
+
+impl PartialOrd for GateWakeupEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for GateWakeupEvent {
+    fn eq(&self, other: &Self) -> bool {
+        (self.time, self.seq) == (other.time, other.seq)
+    }
+}
+impl Eq for GateWakeupEvent {}
+
 #[derive(Debug)]
 pub struct GateUpdateEvent {
-    pub sender_pin_id: usize,
+    /// `None` if the update wasn't caused by a pin, e.g. a wakeup.
+    pub sender_pin_id: Option<usize>,
     pub target_gate_id: usize,
 }
 
@@ -37,4 +70,43 @@ pub struct LumpUpdateEvent {
     pub sender_pin_id: usize,
     pub target_lump_id: usize,
     pub bits: Bits,
+    pub strength: Strength,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BinaryHeap;
+
+    use super::*;
+
+    #[test]
+    fn test_same_time_is_first_in_first_out() {
+        let mut pins = BinaryHeap::new();
+        for (time, seq, target_pin_id) in [(2, 1, 0), (1, 2, 1), (1, 3, 2), (2, 4, 3), (1, 5, 4)] {
+            pins.push(PinUpdateEvent {
+                time,
+                seq,
+                target_pin_id,
+                value: Bits::new(1),
+                drive: false,
+            });
+        }
+        let order: Vec<usize> = std::iter::from_fn(|| pins.pop())
+            .map(|e| e.target_pin_id)
+            .collect();
+        assert_eq!(order, [1, 2, 4, 0, 3]);
+
+        let mut wakeups = BinaryHeap::new();
+        for (time, seq, target_gate_id) in [(3, 1, 10), (3, 2, 11), (0, 3, 12)] {
+            wakeups.push(GateWakeupEvent {
+                time,
+                seq,
+                target_gate_id,
+            });
+        }
+        let order: Vec<usize> = std::iter::from_fn(|| wakeups.pop())
+            .map(|e| e.target_gate_id)
+            .collect();
+        assert_eq!(order, [12, 10, 11]);
+    }
 }
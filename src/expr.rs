@@ -0,0 +1,577 @@
+//! Components defined by Boolean equations, e.g.
+//!
+//! ```text
+//! input a, b, cin;
+//! input [3:0] d;
+//! output sum, carry, low;
+//! sum = a ^ b ^ cin;
+//! carry = a&b | cin&(a^b);
+//! low = ~|d[1:0];
+//! ```
+//!
+//! The syntax follows Verilog: `~` (or `!`) binds tightest, then `&`, `^`
+//! and `|`. A unary `&`, `|` or `^` reduces a signal to one bit, `{a, b}`
+//! concatenates with `a` on top, `{4{a}}` repeats `a` and `d[3:1]` or
+//! `d[0]` select bits.
+//! Constants are `0`, `1` or sized literals like `4'b10x1`. Statements end
+//! with `;`, `//` and `#` start comments.
+//!
+//! Operand widths must match, every output bit must be assigned exactly
+//! once and only inputs can be read.
+
+use crate::{bits::Bits, truth_table::check_signal_name};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// Bits `start..end` of an input.
+    Input {
+        input: usize,
+        start: usize,
+        end: usize,
+    },
+    Const(Bits),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Xor(Box<Expr>, Box<Expr>),
+    /// Parts as written, the first one on top.
+    Concat(Vec<Expr>),
+    AndReduce(Box<Expr>),
+    OrReduce(Box<Expr>),
+    XorReduce(Box<Expr>),
+}
+
+impl Expr {
+    pub fn width(&self) -> usize {
+        match self {
+            Expr::Input { start, end, .. } => end - start,
+            Expr::Const(bits) => bits.len(),
+            Expr::Not(e) => e.width(),
+            Expr::And(a, _) | Expr::Or(a, _) | Expr::Xor(a, _) => a.width(),
+            Expr::Concat(parts) => parts.iter().map(Expr::width).sum(),
+            Expr::AndReduce(_) | Expr::OrReduce(_) | Expr::XorReduce(_) => 1,
+        }
+    }
+
+    /// The value for the given input values, X propagating like in gates.
+    pub fn evaluate(&self, inputs: &[Bits]) -> Bits {
+        let one = |lv| std::iter::once(lv).collect();
+        match self {
+            Expr::Input { input, start, end } => inputs[*input].slice(*start, *end),
+            Expr::Const(bits) => bits.clone(),
+            Expr::Not(e) => e.evaluate(inputs).not(),
+            Expr::And(a, b) => a.evaluate(inputs).and(&b.evaluate(inputs)),
+            Expr::Or(a, b) => a.evaluate(inputs).or(&b.evaluate(inputs)),
+            Expr::Xor(a, b) => a.evaluate(inputs).xor(&b.evaluate(inputs)),
+            Expr::Concat(parts) => {
+                let values: Vec<Bits> = parts.iter().rev().map(|p| p.evaluate(inputs)).collect();
+                Bits::concat_all(&values)
+            }
+            Expr::AndReduce(e) => one(e.evaluate(inputs).and_reduce()),
+            Expr::OrReduce(e) => one(e.evaluate(inputs).or_reduce()),
+            Expr::XorReduce(e) => one(e.evaluate(inputs).xor_reduce()),
+        }
+    }
+}
+
+/// `expr` drives bits `start..end` of an output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    pub output: usize,
+    pub start: usize,
+    pub end: usize,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equations {
+    inputs: Vec<(String, usize)>,
+    outputs: Vec<(String, usize)>,
+    assignments: Vec<Assignment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Name(String),
+    Number(String),
+    Symbol(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Name(s) | Token::Number(s) => write!(f, "'{}'", s),
+            Token::Symbol(c) => write!(f, "'{}'", c),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    for line in text.lines() {
+        let line = line.split("//").next().unwrap().split('#').next().unwrap();
+        let mut chars = line.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            let mut take_while = |pred: fn(char) -> bool| {
+                let mut s = String::new();
+                while let Some(&c) = chars.peek().filter(|c| pred(**c)) {
+                    s.push(c);
+                    chars.next();
+                }
+                s
+            };
+            if c.is_whitespace() {
+                chars.next();
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                tokens.push(Token::Name(take_while(|c| {
+                    c.is_ascii_alphanumeric() || c == '_'
+                })));
+            } else if c.is_ascii_digit() || c == '\'' {
+                // Sized literals continue after the apostrophe with a radix
+                // and digits
+                let number =
+                    take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '\'' || c == '?');
+                tokens.push(Token::Number(number));
+            } else if "=;()[]{},:&|^~!".contains(c) {
+                tokens.push(Token::Symbol(c));
+                chars.next();
+            } else {
+                return Err(format!("Unexpected character '{}'", c));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn check_name(name: &str, ports: &[(String, usize)]) -> Result<(), String> {
+    check_signal_name(name)?;
+    if ports.iter().any(|(n, _)| n == name) {
+        return Err(format!("Signal {} is declared twice", name));
+    }
+    Ok(())
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or("Unexpected end of the equations")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        match self.next()? {
+            Token::Symbol(c) if c == symbol => Ok(()),
+            token => Err(format!("Expected '{}' but found {}", symbol, token)),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Name(name) => Ok(name),
+            token => Err(format!("Expected a name but found {}", token)),
+        }
+    }
+
+    fn index(&mut self) -> Result<usize, String> {
+        match self.next()? {
+            Token::Number(n) => n.parse().map_err(|_| format!("Invalid index {}", n)),
+            token => Err(format!("Expected an index but found {}", token)),
+        }
+    }
+
+    /// An optional `[hi:lo]` or `[i]`, as the range `lo..hi + 1`.
+    fn range(&mut self) -> Result<Option<(usize, usize)>, String> {
+        if !self.eat('[') {
+            return Ok(None);
+        }
+        let high = self.index()?;
+        let low = if self.eat(':') { self.index()? } else { high };
+        self.expect(']')?;
+        if low > high {
+            return Err(format!(
+                "Range [{}:{}] must run from high to low",
+                high, low
+            ));
+        }
+        Ok(Some((low, high + 1)))
+    }
+}
+
+/// The part `range` of a signal `width` bits wide, all of it by default.
+fn select(
+    name: &str,
+    width: usize,
+    range: Option<(usize, usize)>,
+) -> Result<(usize, usize), String> {
+    match range {
+        None => Ok((0, width)),
+        Some((start, end)) if end <= width => Ok((start, end)),
+        Some((_, end)) => Err(format!(
+            "Bit {} is outside of {}, which has {} bits",
+            end - 1,
+            name,
+            width
+        )),
+    }
+}
+
+impl Equations {
+    /// Reads equations that declare their ports with `input` and `output`
+    /// statements before the first assignment.
+    pub fn parse(text: &str) -> Result<Equations, String> {
+        Equations::with_ports(&[], &[], text)
+    }
+
+    /// Reads equations for the given ports, `(name, width)` each. The text
+    /// may declare more.
+    pub fn with_ports(
+        inputs: &[(&str, usize)],
+        outputs: &[(&str, usize)],
+        text: &str,
+    ) -> Result<Equations, String> {
+        let mut equations = Equations {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            assignments: Vec::new(),
+        };
+        for (name, width) in inputs {
+            equations.declare(true, name, *width)?;
+        }
+        for (name, width) in outputs {
+            equations.declare(false, name, *width)?;
+        }
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+        };
+        while let Some(token) = parser.peek().cloned() {
+            if parser.eat(';') {
+                continue;
+            }
+            match token {
+                Token::Name(keyword) if keyword == "input" || keyword == "output" => {
+                    if !equations.assignments.is_empty() {
+                        return Err("Declare all ports before the equations".to_string());
+                    }
+                    parser.pos += 1;
+                    equations.declaration(&mut parser, keyword == "input")?;
+                }
+                _ => equations.assignment(&mut parser)?,
+            }
+            if parser.peek().is_some() {
+                parser.expect(';')?;
+            }
+        }
+        equations.check_assigned()?;
+        Ok(equations)
+    }
+
+    fn declare(&mut self, input: bool, name: &str, width: usize) -> Result<(), String> {
+        check_name(name, &self.inputs)?;
+        check_name(name, &self.outputs)?;
+        if width == 0 {
+            return Err(format!("Signal {} needs at least one bit", name));
+        }
+        let ports = if input {
+            &mut self.inputs
+        } else {
+            &mut self.outputs
+        };
+        ports.push((name.to_string(), width));
+        Ok(())
+    }
+
+    /// `[hi:0] name, name, ...` after `input` or `output`.
+    fn declaration(&mut self, parser: &mut Parser, input: bool) -> Result<(), String> {
+        let width = match parser.range()? {
+            None => 1,
+            Some((0, end)) => end,
+            Some((start, _)) => return Err(format!("Ports must start at bit 0, not {}", start)),
+        };
+        loop {
+            let name = parser.name()?;
+            self.declare(input, &name, width)?;
+            if !parser.eat(',') {
+                return Ok(());
+            }
+        }
+    }
+
+    fn assignment(&mut self, parser: &mut Parser) -> Result<(), String> {
+        let name = parser.name()?;
+        let output = self
+            .outputs
+            .iter()
+            .position(|(n, _)| *n == name)
+            .ok_or(format!("{} is not an output", name))?;
+        let (start, end) = select(&name, self.outputs[output].1, parser.range()?)?;
+        parser.expect('=')?;
+        let expr = self.or_expr(parser)?;
+        if expr.width() != end - start {
+            return Err(format!(
+                "Assigning {} bits to {} bits of {}",
+                expr.width(),
+                end - start,
+                name
+            ));
+        }
+        self.assignments.push(Assignment {
+            output,
+            start,
+            end,
+            expr,
+        });
+        Ok(())
+    }
+
+    fn binary(
+        &self,
+        parser: &mut Parser,
+        symbol: char,
+        operand: fn(&Self, &mut Parser) -> Result<Expr, String>,
+        combine: fn(Box<Expr>, Box<Expr>) -> Expr,
+    ) -> Result<Expr, String> {
+        let mut expr = operand(self, parser)?;
+        while parser.eat(symbol) {
+            let right = operand(self, parser)?;
+            if expr.width() != right.width() {
+                return Err(format!(
+                    "Operands of {} have {} and {} bits",
+                    symbol,
+                    expr.width(),
+                    right.width()
+                ));
+            }
+            expr = combine(Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn or_expr(&self, parser: &mut Parser) -> Result<Expr, String> {
+        self.binary(parser, '|', Self::xor_expr, Expr::Or)
+    }
+
+    fn xor_expr(&self, parser: &mut Parser) -> Result<Expr, String> {
+        self.binary(parser, '^', Self::and_expr, Expr::Xor)
+    }
+
+    fn and_expr(&self, parser: &mut Parser) -> Result<Expr, String> {
+        self.binary(parser, '&', Self::unary, Expr::And)
+    }
+
+    fn unary(&self, parser: &mut Parser) -> Result<Expr, String> {
+        let reduce: Option<fn(Box<Expr>) -> Expr> = match parser.peek() {
+            Some(Token::Symbol('~' | '!')) => Some(Expr::Not),
+            Some(Token::Symbol('&')) => Some(Expr::AndReduce),
+            Some(Token::Symbol('|')) => Some(Expr::OrReduce),
+            Some(Token::Symbol('^')) => Some(Expr::XorReduce),
+            _ => None,
+        };
+        match reduce {
+            Some(op) => {
+                parser.pos += 1;
+                Ok(op(Box::new(self.unary(parser)?)))
+            }
+            None => self.primary(parser),
+        }
+    }
+
+    fn primary(&self, parser: &mut Parser) -> Result<Expr, String> {
+        match parser.next()? {
+            Token::Name(name) => {
+                let Some(input) = self.inputs.iter().position(|(n, _)| *n == name) else {
+                    return Err(match self.outputs.iter().any(|(n, _)| *n == name) {
+                        true => format!("Output {} can't be read", name),
+                        false => format!("Unknown signal {}", name),
+                    });
+                };
+                let (start, end) = select(&name, self.inputs[input].1, parser.range()?)?;
+                Ok(Expr::Input { input, start, end })
+            }
+            Token::Number(number) => match number.as_str() {
+                "0" | "1" => Ok(Expr::Const(Bits::from_u64(1, number.parse().unwrap())?)),
+                _ if number.contains('\'') && !number.starts_with('\'') => {
+                    Ok(Expr::Const(number.parse()?))
+                }
+                _ => Err(format!(
+                    "Constant {} needs a size, e.g. 4'd{}",
+                    number, number
+                )),
+            },
+            Token::Symbol('(') => {
+                let expr = self.or_expr(parser)?;
+                parser.expect(')')?;
+                Ok(expr)
+            }
+            Token::Symbol('{') => {
+                if let (Some(Token::Number(_)), Some(Token::Symbol('{'))) =
+                    (parser.peek(), parser.tokens.get(parser.pos + 1))
+                {
+                    // `{n{x}}` repeats x
+                    let count = parser.index()?;
+                    parser.expect('{')?;
+                    let expr = self.or_expr(parser)?;
+                    parser.expect('}')?;
+                    parser.expect('}')?;
+                    if count == 0 {
+                        return Err("Can't repeat something 0 times".to_string());
+                    }
+                    return Ok(Expr::Concat(vec![expr; count]));
+                }
+                let mut parts = vec![self.or_expr(parser)?];
+                while parser.eat(',') {
+                    parts.push(self.or_expr(parser)?);
+                }
+                parser.expect('}')?;
+                Ok(Expr::Concat(parts))
+            }
+            token => Err(format!("Unexpected {}", token)),
+        }
+    }
+
+    fn check_assigned(&self) -> Result<(), String> {
+        for (output, (name, width)) in self.outputs.iter().enumerate() {
+            let mut assigned = vec![false; *width];
+            for a in self.assignments.iter().filter(|a| a.output == output) {
+                for (bit, assigned) in assigned.iter_mut().enumerate().take(a.end).skip(a.start) {
+                    if *assigned {
+                        return Err(format!("Bit {} of {} is assigned twice", bit, name));
+                    }
+                    *assigned = true;
+                }
+            }
+            if let Some(bit) = assigned.iter().position(|a| !a) {
+                return Err(format!("Bit {} of {} is never assigned", bit, name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn inputs(&self) -> &[(String, usize)] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[(String, usize)] {
+        &self.outputs
+    }
+
+    pub fn assignments(&self) -> &[Assignment] {
+        &self.assignments
+    }
+
+    /// The output values for the given input values, in declaration order.
+    pub fn evaluate(&self, inputs: &[Bits]) -> Vec<Bits> {
+        let mut outputs: Vec<Bits> = self
+            .outputs
+            .iter()
+            .map(|(_, width)| Bits::new(*width))
+            .collect();
+        for a in &self.assignments {
+            let value = a.expr.evaluate(inputs);
+            for (i, lv) in value.iter().enumerate() {
+                outputs[a.output].set(a.start + i, lv);
+            }
+        }
+        outputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(literals: &[&str]) -> Vec<Bits> {
+        literals.iter().map(|l| l.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_full_adder() {
+        let eqs = Equations::with_ports(
+            &[("a", 1), ("b", 1), ("cin", 1)],
+            &[("sum", 1), ("carry", 1)],
+            "sum = a ^ b ^ cin; carry = a&b | cin&(a^b)",
+        )
+        .unwrap();
+        for n in 0..8u64 {
+            let inputs: Vec<Bits> = (0..3)
+                .map(|i| Bits::from_u64(1, n >> i & 1).unwrap())
+                .collect();
+            let total = n.count_ones() as u64;
+            let outputs = eqs.evaluate(&inputs);
+            assert_eq!(outputs[0].to_u64(), Ok(total & 1));
+            assert_eq!(outputs[1].to_u64(), Ok(total >> 1));
+        }
+        let outputs = eqs.evaluate(&values(&["1'b1", "1'b1", "1'bx"]));
+        assert_eq!(
+            (outputs[0].to_string(), outputs[1].to_string()),
+            ("X".into(), "1".into())
+        );
+    }
+
+    #[test]
+    fn test_buses_and_slices() {
+        let text = "
+            input [3:0] d; input s;
+            output [3:0] y; output p, low;
+            // swap the halves unless s is set
+            y[3:2] = d[1:0] & {2{s}} | d[3:2] & ~{s, s};
+            y[1:0] = d[1:0];
+            p = ^d;   # parity
+            low = ~|d[1:0] & 1'b1;
+        ";
+        let eqs = Equations::parse(text).unwrap();
+        let outputs = eqs.evaluate(&values(&["4'b0110", "1'b0"]));
+        assert_eq!(outputs[0], "4'b0110".parse().unwrap());
+        assert_eq!(outputs[1].to_string(), "0");
+        assert_eq!(outputs[2].to_string(), "0");
+        let outputs = eqs.evaluate(&values(&["4'b0100", "1'b1"]));
+        assert_eq!(outputs[0], "4'b0000".parse().unwrap());
+        assert_eq!(outputs[2].to_string(), "1");
+        let eqs = Equations::parse(&text.replace("{2{s}}", "{s, s}")).unwrap();
+        assert_eq!(eqs.evaluate(&values(&["4'b0100", "1'b1"]))[0], outputs[0]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let ports =
+            |text| Equations::with_ports(&[("a", 1), ("d", 4)], &[("y", 1)], text).unwrap_err();
+        assert_eq!(ports("y = b"), "Unknown signal b");
+        assert_eq!(ports("y = a & d"), "Operands of & have 1 and 4 bits");
+        assert_eq!(ports("y = d[1:0]"), "Assigning 2 bits to 1 bits of y");
+        assert_eq!(ports("y = d[4]"), "Bit 4 is outside of d, which has 4 bits");
+        assert_eq!(ports("y = y"), "Output y can't be read");
+        assert_eq!(ports("y = a; y = a"), "Bit 0 of y is assigned twice");
+        assert_eq!(ports(""), "Bit 0 of y is never assigned");
+        assert_eq!(ports("y = a +"), "Unexpected character '+'");
+        assert_eq!(ports("y = 2"), "Constant 2 needs a size, e.g. 4'd2");
+        assert_eq!(ports("y = (a"), "Unexpected end of the equations");
+        assert_eq!(ports("a = a"), "a is not an output");
+        assert_eq!(
+            ports("y = a; input b"),
+            "Declare all ports before the equations"
+        );
+        assert_eq!(ports("input a"), "Signal a is declared twice");
+    }
+}
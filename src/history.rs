@@ -0,0 +1,455 @@
+use std::collections::HashMap;
+
+use tracing::{info, instrument, warn};
+
+use crate::{notify::Notification, params::Param, ComponentManager, GenericGate, Lump, Pin};
+
+/// A single reversible structural change of the netlist.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    CreateGate {
+        gate_id: usize,
+    },
+    DeleteGate {
+        gate_id: usize,
+    },
+    CreateLump {
+        lump_id: usize,
+    },
+    DeleteLump {
+        lump_id: usize,
+    },
+    Connect {
+        pin_id: usize,
+        lump_id: usize,
+    },
+    Disconnect {
+        pin_id: usize,
+        lump_id: usize,
+    },
+    SetParam {
+        gate_id: usize,
+        name: String,
+        old: Option<Param>,
+        new: Option<Param>,
+    },
+}
+
+impl Edit {
+    pub fn inverse(&self) -> Edit {
+        match self.clone() {
+            Edit::CreateGate { gate_id } => Edit::DeleteGate { gate_id },
+            Edit::DeleteGate { gate_id } => Edit::CreateGate { gate_id },
+            Edit::CreateLump { lump_id } => Edit::DeleteLump { lump_id },
+            Edit::DeleteLump { lump_id } => Edit::CreateLump { lump_id },
+            Edit::Connect { pin_id, lump_id } => Edit::Disconnect { pin_id, lump_id },
+            Edit::Disconnect { pin_id, lump_id } => Edit::Connect { pin_id, lump_id },
+            Edit::SetParam {
+                gate_id,
+                name,
+                old,
+                new,
+            } => Edit::SetParam {
+                gate_id,
+                name,
+                old: new,
+                new: old,
+            },
+        }
+    }
+}
+
+/// A gate that was removed from the netlist together with its pins. Kept
+/// around so undo/redo can put back the very same objects and ids.
+#[derive(Debug)]
+pub(crate) struct DetachedGate {
+    pub(crate) gate: GenericGate,
+    pub(crate) pins: Vec<Pin>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct EditLog {
+    undo_stack: Vec<Vec<Edit>>,
+    redo_stack: Vec<Vec<Edit>>,
+    open: Vec<Edit>,
+    /// Where each open transaction starts in `open`, innermost last.
+    marks: Vec<usize>,
+    replaying: bool,
+    pub(crate) detached_gates: HashMap<usize, DetachedGate>,
+    pub(crate) detached_lumps: HashMap<usize, Lump>,
+}
+
+impl EditLog {
+    pub(crate) fn record(&mut self, edit: Edit) {
+        if self.replaying {
+            return;
+        }
+        self.discard_redo();
+        if !self.marks.is_empty() {
+            self.open.push(edit);
+        } else {
+            self.undo_stack.push(vec![edit]);
+        }
+    }
+
+    fn begin(&mut self) {
+        self.marks.push(self.open.len());
+    }
+
+    fn commit(&mut self) -> Result<(), String> {
+        if self.marks.pop().is_none() {
+            return Err("There is no open transaction to commit".to_string());
+        }
+        if self.marks.is_empty() && !self.open.is_empty() {
+            let group = std::mem::take(&mut self.open);
+            self.undo_stack.push(group);
+        }
+        Ok(())
+    }
+
+    /// Once a new edit is made the undone groups can't be redone anymore, so
+    /// everything only they were keeping alive gets dropped.
+    fn discard_redo(&mut self) {
+        for group in std::mem::take(&mut self.redo_stack) {
+            for edit in group {
+                match edit {
+                    Edit::CreateGate { gate_id } => {
+                        self.detached_gates.remove(&gate_id);
+                    }
+                    Edit::CreateLump { lump_id } => {
+                        self.detached_lumps.remove(&lump_id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl ComponentManager {
+    /// Groups all following edits into one undo step until the matching
+    /// `commit_transaction`. Transactions may be nested.
+    pub fn begin_transaction(&mut self) {
+        self.history.begin();
+    }
+
+    pub fn commit_transaction(&mut self) -> Result<(), String> {
+        self.history.commit()
+    }
+
+    /// Closes the innermost transaction and reverts every edit made in it
+    /// without leaving an undo step behind.
+    #[instrument(skip(self))]
+    pub fn abort_transaction(&mut self) -> Result<(), String> {
+        let start = self
+            .history
+            .marks
+            .pop()
+            .ok_or("There is no open transaction to abort")?;
+        let edits = self.history.open.split_off(start);
+        info!("Aborting {} edits", edits.len());
+        self.history.replaying = true;
+        let result = edits
+            .iter()
+            .rev()
+            .try_for_each(|edit| self.apply_edit(&edit.inverse()));
+        self.history.replaying = false;
+        // Nothing can bring the created objects back anymore
+        for edit in &edits {
+            match edit {
+                Edit::CreateGate { gate_id } => {
+                    self.history.detached_gates.remove(gate_id);
+                }
+                Edit::CreateLump { lump_id } => {
+                    self.history.detached_lumps.remove(lump_id);
+                }
+                _ => {}
+            }
+        }
+        result
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo_stack.is_empty()
+    }
+
+    /// Forgets all recorded edits including the objects kept for restoring
+    /// deleted gates and lumps.
+    pub fn clear_history(&mut self) {
+        self.history = EditLog::default();
+    }
+
+    /// Reverts the last transaction. Returns `false` if there was nothing to
+    /// undo.
+    #[instrument(skip(self))]
+    pub fn undo(&mut self) -> Result<bool, String> {
+        if !self.history.marks.is_empty() {
+            return Err("Can't undo while a transaction is open".to_string());
+        }
+        let Some(group) = self.history.undo_stack.pop() else {
+            return Ok(false);
+        };
+        info!("Undoing {} edits", group.len());
+        let inverses: Vec<Edit> = group.iter().rev().map(Edit::inverse).collect();
+        if let Err(e) = self.replay(&inverses) {
+            self.history.undo_stack.push(group);
+            return Err(e);
+        }
+        self.history.redo_stack.push(group);
+        Ok(true)
+    }
+
+    /// Re-applies the last undone transaction. Returns `false` if there was
+    /// nothing to redo.
+    #[instrument(skip(self))]
+    pub fn redo(&mut self) -> Result<bool, String> {
+        if !self.history.marks.is_empty() {
+            return Err("Can't redo while a transaction is open".to_string());
+        }
+        let Some(group) = self.history.redo_stack.pop() else {
+            return Ok(false);
+        };
+        info!("Redoing {} edits", group.len());
+        if let Err(e) = self.replay(&group) {
+            self.history.redo_stack.push(group);
+            return Err(e);
+        }
+        self.history.undo_stack.push(group);
+        Ok(true)
+    }
+
+    /// Applies `edits` without recording them. If one fails the ones before
+    /// it are reverted, so a group is either replayed fully or not at all.
+    fn replay(&mut self, edits: &[Edit]) -> Result<(), String> {
+        self.history.replaying = true;
+        let mut result = Ok(());
+        for (done, edit) in edits.iter().enumerate() {
+            if let Err(e) = self.apply_edit(edit) {
+                for edit in edits[..done].iter().rev() {
+                    if let Err(e) = self.apply_edit(&edit.inverse()) {
+                        warn!("Couldn't revert {:?}: {}", edit, e);
+                    }
+                }
+                result = Err(e);
+                break;
+            }
+        }
+        self.history.replaying = false;
+        result
+    }
+
+    fn apply_edit(&mut self, edit: &Edit) -> Result<(), String> {
+        match edit {
+            Edit::CreateGate { gate_id } => {
+                let detached = self
+                    .history
+                    .detached_gates
+                    .remove(gate_id)
+                    .ok_or(format!("Gate with id {} can't be restored", gate_id))?;
+                self.attach_gate(detached);
+            }
+            Edit::DeleteGate { gate_id } => {
+                let detached = self.detach_gate(gate_id)?;
+                self.history.detached_gates.insert(*gate_id, detached);
+            }
+            Edit::CreateLump { lump_id } => {
+                let lump = self
+                    .history
+                    .detached_lumps
+                    .remove(lump_id)
+                    .ok_or(format!("Lump with id {} can't be restored", lump_id))?;
+                self.accept_lump(lump);
+            }
+            Edit::DeleteLump { lump_id } => {
+                let lump = self
+                    .lumps
+                    .write()
+                    .unwrap()
+                    .remove(lump_id)
+                    .ok_or(format!("The lump with id {} doesn't exist", lump_id))?;
+                self.history.detached_lumps.insert(*lump_id, lump);
+            }
+            Edit::Connect { pin_id, lump_id } => self.connect_pin_to_lump(pin_id, lump_id)?,
+            Edit::Disconnect { pin_id, lump_id } => self.disconnect_pin_from_lump(pin_id, lump_id),
+            Edit::SetParam {
+                gate_id, name, new, ..
+            } => {
+                self.write_gate_param(gate_id, name, new.clone())?;
+            }
+        }
+        if !matches!(edit, Edit::Connect { .. } | Edit::Disconnect { .. }) {
+            self.notify(Notification::Topology(edit.clone()));
+            self.flush_notifications();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bits::Bits, params::Param, run::RunBudget, ComponentManager, PinType};
+
+    fn connections(cm: &ComponentManager) -> Vec<(usize, Option<usize>)> {
+        let mut pins: Vec<_> = cm
+            .pins
+            .read()
+            .unwrap()
+            .values()
+            .map(|p| (p.id, p.lump_id))
+            .collect();
+        pins.sort();
+        pins
+    }
+
+    #[test]
+    fn test_undo_redo_create_and_connect() {
+        let mut cm = ComponentManager::new();
+        let and = cm.create_gate("and").unwrap();
+        let lump = cm.create_lump(1);
+        cm.connect_gate_pin_to_lump(&and, &0, &PinType::OUT, &lump)
+            .unwrap();
+        let connected = connections(&cm);
+
+        assert!(cm.undo().unwrap());
+        assert!(connections(&cm).iter().all(|(_, l)| l.is_none()));
+        assert!(cm.undo().unwrap());
+        assert!(cm.lumps.read().unwrap().is_empty());
+        assert!(cm.undo().unwrap());
+        assert!(cm.gates.read().unwrap().is_empty());
+        assert!(cm.pins.read().unwrap().is_empty());
+        assert!(!cm.undo().unwrap());
+
+        while cm.redo().unwrap() {}
+        assert_eq!(connections(&cm), connected);
+        assert!(cm.gates.read().unwrap().contains_key(&and));
+    }
+
+    #[test]
+    fn test_delete_gate_restores_connections() {
+        let mut cm = ComponentManager::new();
+        let not = cm.create_gate("not").unwrap();
+        let lump_in = cm.create_lump(1);
+        let lump_out = cm.create_lump(1);
+        cm.connect_gate_pin_to_lump(&not, &0, &PinType::IN, &lump_in)
+            .unwrap();
+        cm.connect_gate_pin_to_lump(&not, &0, &PinType::OUT, &lump_out)
+            .unwrap();
+        let connected = connections(&cm);
+
+        cm.delete_gate(&not).unwrap();
+        assert!(cm.pins.read().unwrap().is_empty());
+        assert!(cm.lumps.read().unwrap()[&lump_in].pin_ids.is_empty());
+
+        assert!(cm.undo().unwrap());
+        assert_eq!(connections(&cm), connected);
+        assert_eq!(cm.lumps.read().unwrap()[&lump_out].pin_ids.len(), 1);
+
+        assert!(cm.redo().unwrap());
+        assert!(cm.gates.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_transaction_is_one_step() {
+        let mut cm = ComponentManager::new();
+        cm.begin_transaction();
+        let or = cm.create_gate("or").unwrap();
+        let lump = cm.create_lump(1);
+        cm.connect_gate_pin_to_lump(&or, &1, &PinType::IN, &lump)
+            .unwrap();
+        cm.commit_transaction().unwrap();
+
+        assert!(cm.undo().unwrap());
+        assert!(cm.gates.read().unwrap().is_empty());
+        assert!(cm.lumps.read().unwrap().is_empty());
+        assert!(!cm.can_undo());
+        assert!(cm.commit_transaction().is_err());
+    }
+
+    #[test]
+    fn test_undo_param_and_delete_lump() {
+        let mut cm = ComponentManager::new();
+        let and = cm.create_gate("and").unwrap();
+        let lump = cm.create_lump(1);
+        cm.connect_gate_pin_to_lump(&and, &0, &PinType::IN, &lump)
+            .unwrap();
+        cm.set_gate_param(&and, "delay", Param::Int(3)).unwrap();
+        cm.set_gate_param(&and, "delay", Param::Int(5)).unwrap();
+        cm.delete_lump(&lump).unwrap();
+        assert!(connections(&cm).iter().all(|(_, l)| l.is_none()));
+
+        cm.undo().unwrap();
+        assert_eq!(cm.get_lump_value(&lump), Bits::new(1));
+        assert!(connections(&cm).iter().any(|(_, l)| l == &Some(lump)));
+        cm.undo().unwrap();
+        assert_eq!(cm.get_gate_param(&and, "delay"), Some(Param::Int(3)));
+        cm.undo().unwrap();
+        assert_eq!(cm.get_gate_param(&and, "delay"), None);
+
+        // a new edit drops the redo branch
+        cm.set_gate_param(&and, "delay", Param::Int(1)).unwrap();
+        assert!(!cm.can_redo());
+    }
+
+    #[test]
+    fn test_failed_undo_keeps_the_group() {
+        let mut cm = ComponentManager::new();
+        let a = cm.create_gate("and").unwrap();
+        let b = cm.create_gate("or").unwrap();
+        cm.begin_transaction();
+        cm.delete_gate(&a).unwrap();
+        cm.delete_gate(&b).unwrap();
+        cm.commit_transaction().unwrap();
+
+        // Undo restores b first and then fails on a
+        let detached = cm.history.detached_gates.remove(&a).unwrap();
+        assert!(cm.undo().is_err());
+        assert!(cm.gates.read().unwrap().is_empty());
+        assert!(cm.history.detached_gates.contains_key(&b));
+        assert!(!cm.can_redo());
+
+        cm.history.detached_gates.insert(a, detached);
+        assert!(cm.undo().unwrap());
+        assert_eq!(cm.gates.read().unwrap().len(), 2);
+
+        // Redo deletes a first and then fails on b
+        let detached = cm.detach_gate(&b).unwrap();
+        assert!(cm.redo().is_err());
+        assert!(cm.gates.read().unwrap().contains_key(&a));
+        assert!(!cm.history.detached_gates.contains_key(&a));
+        assert!(cm.can_redo());
+        assert_eq!(cm.history.undo_stack.len(), 2);
+
+        cm.attach_gate(detached);
+        assert!(cm.redo().unwrap());
+        assert!(cm.gates.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_undo_restores_driven_values() {
+        let mut cm = ComponentManager::new();
+        let vcc = cm.create_gate("vcc").unwrap();
+        let lump = cm.create_lump(1);
+        cm.connect_gate_pin_to_lump(&vcc, &0, &PinType::OUT, &lump)
+            .unwrap();
+        cm.run_budgeted(&RunBudget::default());
+        let high = Bits::new(1).set_num(1);
+        assert_eq!(cm.get_lump_value(&lump), high);
+
+        let pin = cm.get_gate_pins(&vcc, &PinType::OUT)[0];
+        cm.disconnect_pin_from_lump(&pin, &lump);
+        assert_eq!(cm.get_lump_value(&lump), Bits::new(1));
+        assert!(cm.undo().unwrap());
+        assert_eq!(cm.get_lump_value(&lump), high);
+
+        cm.delete_gate(&vcc).unwrap();
+        assert_eq!(cm.get_lump_value(&lump), Bits::new(1));
+        assert!(cm.undo().unwrap());
+        assert_eq!(cm.get_lump_value(&lump), high);
+        assert!(cm.redo().unwrap());
+        assert_eq!(cm.get_lump_value(&lump), Bits::new(1));
+    }
+}
@@ -1,64 +1,261 @@
+mod components;
 mod events;
-use events::{GateUpdateEvent, LumpUpdateEvent, PinUpdateEvent};
+pub mod expr;
+pub mod history;
+pub mod memfile;
+pub mod minimize;
+pub mod notify;
+pub mod params;
+pub mod realtime;
+pub mod run;
+pub mod std_logic;
+pub mod synth;
+pub mod truth_table;
+pub mod wasm;
+use events::{GateUpdateEvent, GateWakeupEvent, LumpUpdateEvent, PinUpdateEvent};
+use expr::Equations;
+use history::{DetachedGate, Edit, EditLog};
+use notify::{Notification, Observers};
+use params::{Param, Params};
 use std::{
     cell::Cell,
     collections::{BinaryHeap, HashMap, VecDeque},
     sync::{Arc, RwLock},
 };
+use truth_table::TruthTable;
 
-use bits::Bits;
-use tracing::{info, instrument, warn};
+use bits::{resolve_drivers, Bits, Strength};
+use tracing::{debug, info, instrument, warn};
 pub mod bits;
 
 #[derive(Debug)]
 pub struct ComponentManager {
     counter: Cell<usize>,
+    event_counter: Cell<u64>,
     current_sim_time: u64,
     component_library: ComponentLibrary,
     pin_update_queue: RwLock<BinaryHeap<PinUpdateEvent>>,
+    gate_wakeup_queue: RwLock<BinaryHeap<GateWakeupEvent>>,
     gate_update_queue: RwLock<VecDeque<GateUpdateEvent>>,
     lump_update_queue: RwLock<VecDeque<LumpUpdateEvent>>,
     pins: RwLock<HashMap<usize, Pin>>,
     lumps: RwLock<HashMap<usize, Lump>>,
     gates: RwLock<HashMap<usize, GenericGate>>,
+    history: EditLog,
+    observers: RwLock<Observers>,
+}
+
+impl Default for ComponentManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ComponentManager {
-    fn new() -> Self {
+    pub fn new() -> Self {
         ComponentManager {
             counter: Cell::new(0),
+            event_counter: Cell::new(0),
             current_sim_time: 0,
             component_library: ComponentLibrary::new(),
             pin_update_queue: RwLock::new(BinaryHeap::new()),
+            gate_wakeup_queue: RwLock::new(BinaryHeap::new()),
             gate_update_queue: RwLock::new(VecDeque::new()),
             lump_update_queue: RwLock::new(VecDeque::new()),
             pins: RwLock::new(HashMap::new()),
             lumps: RwLock::new(HashMap::new()),
             gates: RwLock::new(HashMap::new()),
+            history: EditLog::default(),
+            observers: RwLock::new(Observers::default()),
         }
     }
     fn get_id(&self) -> usize {
         self.counter.set(self.counter.get() + 1);
-        return self.counter.get();
+        self.counter.get()
+    }
+
+    fn next_event_seq(&self) -> u64 {
+        self.event_counter.set(self.event_counter.get() + 1);
+        self.event_counter.get()
     }
 
     #[instrument(ret, skip(self))]
-    fn create_pin(&self, gate_id: usize, n: usize, pin_type: PinType) -> usize {
+    fn create_pin(&self, gate_id: usize, n: usize, pin_type: PinType, strength: Strength) -> usize {
         let id = self.get_id();
-        let p = Pin::new(id, gate_id, n, pin_type);
+        let p = Pin::new(id, gate_id, n, pin_type, strength);
         self.pins.write().unwrap().insert(p.id, p);
-        return id;
+        id
     }
 
     fn accept_gate(&mut self, gate: GenericGate) -> usize {
         let id = gate.id;
+        if gate.initial_update {
+            self.schedule_gate_wakeup(0, id);
+        }
         self.gates.write().unwrap().insert(gate.id, gate);
-        return id;
+        id
+    }
+
+    /// Records a structural change for undo and tells topology observers.
+    fn record_edit(&mut self, edit: Edit) {
+        self.notify(Notification::Topology(edit.clone()));
+        self.history.record(edit);
+        self.flush_notifications();
+    }
+
+    pub fn create_gate(&mut self, name: &str) -> Result<usize, String> {
+        self.create_gate_with_params(name, Params::new())
+    }
+
+    /// Creates a gate whose pins and behaviour are configured by `params`,
+    /// e.g. a bit width or a clock period.
+    pub fn create_gate_with_params(&mut self, name: &str, params: Params) -> Result<usize, String> {
+        let gate = self.component_library.construct_gate(name, params, self)?;
+        let id = self.accept_gate(gate);
+        self.record_edit(Edit::CreateGate { gate_id: id });
+        Ok(id)
+    }
+
+    /// Adds a combinational component computing `table` to the library,
+    /// created by `name` like the built-in ones.
+    pub fn register_truth_table(&mut self, name: &str, table: &TruthTable) -> Result<(), String> {
+        let constructor = components::custom::truth_table(table)?;
+        self.component_library.register(name, constructor)
+    }
+
+    /// Adds a combinational component computing `equations` to the
+    /// library, created by `name` like the built-in ones.
+    pub fn register_equations(&mut self, name: &str, equations: &Equations) -> Result<(), String> {
+        let constructor = components::custom::equations(equations);
+        self.component_library.register(name, constructor)
+    }
+
+    /// Removes a gate and its pins, disconnecting them from their lumps first.
+    #[instrument(skip(self))]
+    pub fn delete_gate(&mut self, gate_id: &usize) -> Result<(), String> {
+        let pin_ids = self
+            .gates
+            .read()
+            .unwrap()
+            .get(gate_id)
+            .ok_or(format!("The gate with id {} doesn't exist", gate_id))?
+            .gpio
+            .all_pins();
+        self.begin_transaction();
+        for pin_id in &pin_ids {
+            let lump_id = self
+                .pins
+                .read()
+                .unwrap()
+                .get(pin_id)
+                .and_then(|p| p.lump_id);
+            if let Some(lump_id) = lump_id {
+                self.disconnect_pin_from_lump(pin_id, &lump_id);
+            }
+        }
+        let detached = self.detach_gate(gate_id)?;
+        self.history.detached_gates.insert(*gate_id, detached);
+        self.record_edit(Edit::DeleteGate { gate_id: *gate_id });
+        self.commit_transaction()
+    }
+
+    fn detach_gate(&mut self, gate_id: &usize) -> Result<DetachedGate, String> {
+        let gate = self
+            .gates
+            .write()
+            .unwrap()
+            .remove(gate_id)
+            .ok_or(format!("The gate with id {} doesn't exist", gate_id))?;
+        let mut pins = self.pins.write().unwrap();
+        let mut lumps = self.lumps.write().unwrap();
+        let pins = gate
+            .gpio
+            .all_pins()
+            .iter()
+            .filter_map(|id| pins.remove(id))
+            .map(|mut pin| {
+                if let Some(lump) = pin.lump_id.and_then(|l| lumps.get_mut(&l)) {
+                    lump.disconnect(&pin.id);
+                }
+                pin.disconnect();
+                pin
+            })
+            .collect();
+        Ok(DetachedGate { gate, pins })
+    }
+
+    fn attach_gate(&mut self, detached: DetachedGate) {
+        let mut pins = self.pins.write().unwrap();
+        for pin in detached.pins {
+            pins.insert(pin.id, pin);
+        }
+        drop(pins);
+        self.accept_gate(detached.gate);
+    }
+
+    /// Name of the library component the gate was created from.
+    pub fn get_gate_kind(&self, gate_id: &usize) -> Option<String> {
+        self.gates
+            .read()
+            .unwrap()
+            .get(gate_id)
+            .map(|g| g.kind.clone())
+    }
+
+    /// Finds the gate whose `label` parameter equals `label`.
+    pub fn find_gate_by_label(&self, label: &str) -> Option<usize> {
+        self.gates
+            .read()
+            .unwrap()
+            .values()
+            .find(|g| matches!(g.params.get("label"), Some(Param::Text(l)) if l == label))
+            .map(|g| g.id)
     }
 
-    fn create_gate(&mut self, name: &str) -> Result<usize, &str> {
-        let gate = self.component_library.construct_gate(name, &self).unwrap();
-        Ok(self.accept_gate(gate))
+    pub fn get_gate_param(&self, gate_id: &usize, name: &str) -> Option<Param> {
+        self.gates
+            .read()
+            .unwrap()
+            .get(gate_id)
+            .and_then(|g| g.params.get(name).cloned())
+    }
+
+    /// Changes a parameter of an existing gate, which then gets updated so
+    /// the new value takes effect. Parameters that shape the pins only count
+    /// at construction.
+    pub fn set_gate_param(
+        &mut self,
+        gate_id: &usize,
+        name: &str,
+        value: Param,
+    ) -> Result<(), String> {
+        let old = self.write_gate_param(gate_id, name, Some(value.clone()))?;
+        self.record_edit(Edit::SetParam {
+            gate_id: *gate_id,
+            name: name.to_string(),
+            old,
+            new: Some(value),
+        });
+        Ok(())
+    }
+
+    fn write_gate_param(
+        &mut self,
+        gate_id: &usize,
+        name: &str,
+        value: Option<Param>,
+    ) -> Result<Option<Param>, String> {
+        let mut gates = self.gates.write().unwrap();
+        let gate = gates
+            .get_mut(gate_id)
+            .ok_or(format!("The gate with id {} doesn't exist", gate_id))?;
+        let old = match value {
+            Some(value) => gate.params.set(name, value),
+            None => gate.params.remove(name),
+        };
+        drop(gates);
+        self.schedule_gate_wakeup(0, *gate_id);
+        Ok(old)
     }
 
     #[instrument(skip(self), ret)]
@@ -77,43 +274,141 @@ impl ComponentManager {
     fn accept_lump(&mut self, lump: Lump) -> usize {
         let id = lump.id;
         self.lumps.write().unwrap().insert(lump.id, lump);
-        return id;
+        id
+    }
+
+    pub fn create_lump(&mut self, n: usize) -> usize {
+        let lump = Lump::new(self.get_id(), n);
+        let id = self.accept_lump(lump);
+        self.record_edit(Edit::CreateLump { lump_id: id });
+        id
     }
 
-    fn create_lump(&mut self, n: usize) -> usize {
-        let lump = Lump::new(self.get_id(), n, self);
-        self.accept_lump(lump)
+    /// Removes a lump after disconnecting all pins attached to it.
+    #[instrument(skip(self))]
+    pub fn delete_lump(&mut self, lump_id: &usize) -> Result<(), String> {
+        let pin_ids = self
+            .lumps
+            .read()
+            .unwrap()
+            .get(lump_id)
+            .ok_or(format!("The lump with id {} doesn't exist", lump_id))?
+            .pin_ids
+            .clone();
+        self.begin_transaction();
+        for pin_id in &pin_ids {
+            self.disconnect_pin_from_lump(pin_id, lump_id);
+        }
+        let lump = self.lumps.write().unwrap().remove(lump_id).unwrap();
+        self.history.detached_lumps.insert(*lump_id, lump);
+        self.record_edit(Edit::DeleteLump { lump_id: *lump_id });
+        self.commit_transaction()
     }
 
-    fn connect_pin_to_lump(&mut self, pin_id: &usize, lump_id: &usize) -> Result<(), String> {
+    /// Connects a pin to a lump of the same width. A pin that is already
+    /// connected elsewhere gets disconnected from its old lump first.
+    pub fn connect_pin_to_lump(&mut self, pin_id: &usize, lump_id: &usize) -> Result<(), String> {
+        let old_lump = self
+            .pins
+            .read()
+            .unwrap()
+            .get(pin_id)
+            .and_then(|p| p.lump_id);
+        if old_lump == Some(*lump_id) {
+            return Ok(());
+        }
+        let widths = (
+            self.pins.read().unwrap().get(pin_id).map(|p| p.value.len()),
+            self.lumps
+                .read()
+                .unwrap()
+                .get(lump_id)
+                .map(|l| l.value.len()),
+        );
+        match widths {
+            (Some(pin_width), Some(lump_width)) if pin_width != lump_width => {
+                return Err(format!(
+                    "Pin {} is {} bits wide but lump {} is {} bits wide",
+                    pin_id, pin_width, lump_id, lump_width
+                ));
+            }
+            (Some(_), Some(_)) => {}
+            _ => {
+                return Err(format!(
+                    "Either pin with id {} or lump with id {} doesn't exist",
+                    pin_id, lump_id,
+                ))
+            }
+        }
+        self.begin_transaction();
+        if let Some(old_lump) = old_lump {
+            self.disconnect_pin_from_lump(pin_id, &old_lump);
+        }
         let mut pins = self.pins.write().unwrap();
-        let pin = pins.get_mut(&pin_id);
+        let pin = pins.get_mut(pin_id);
         let mut lumps = self.lumps.write().unwrap();
-        let lump = lumps.get_mut(&lump_id);
-        if let (Some(pin), Some(lump)) = (pin, lump) {
+        let lump = lumps.get_mut(lump_id);
+        let result = if let (Some(pin), Some(lump)) = (pin, lump) {
             pin.connect(lump_id);
             lump.connect(pin_id);
+            // A driver that already settled keeps driving its last value
+            let driven = match pin.pin_type {
+                PinType::IN => None,
+                PinType::OUT => Some(pin.value.clone()),
+                PinType::INOUT => Some(pin.driven.clone()),
+            };
+            let reads = driven.is_none() || matches!(pin.pin_type, PinType::INOUT);
+            if let Some(bits) = driven {
+                lump.drivers.insert(*pin_id, (bits, pin.strength));
+            }
+            drop(pins);
+            lump.refresh(self);
+            // A new reader sees the lump even if its value didn't change
+            if reads {
+                self.schedule_pin_update(0, *pin_id, lump.value.clone());
+            }
             Ok(())
         } else {
+            drop(pins);
             Err(format!(
                 "Either pin with id {} or lump with id {} doesn't exist",
                 pin_id, lump_id,
             ))
+        };
+        drop(lumps);
+        if result.is_ok() {
+            self.record_edit(Edit::Connect {
+                pin_id: *pin_id,
+                lump_id: *lump_id,
+            });
         }
+        self.commit_transaction()?;
+        result
     }
 
-    fn disconnect_pin_from_lump(&mut self, pin_id: &usize, lump_id: &usize) {
+    pub fn disconnect_pin_from_lump(&mut self, pin_id: &usize, lump_id: &usize) {
         let mut pins = self.pins.write().unwrap();
-        let pin = pins.get_mut(&pin_id);
+        let pin = pins.get_mut(pin_id);
         let mut lumps = self.lumps.write().unwrap();
-        let lump = lumps.get_mut(&lump_id);
+        let lump = lumps.get_mut(lump_id);
         if let (Some(pin), Some(lump)) = (pin, lump) {
+            if pin.lump_id != Some(*lump_id) {
+                return;
+            }
             pin.disconnect();
             lump.disconnect(pin_id);
+            drop(pins);
+            // The remaining drivers decide now
+            lump.refresh(self);
+            drop(lumps);
+            self.record_edit(Edit::Disconnect {
+                pin_id: *pin_id,
+                lump_id: *lump_id,
+            });
         }
     }
 
-    fn connect_gate_pin_to_lump(
+    pub fn connect_gate_pin_to_lump(
         &mut self,
         gate_id: &usize,
         pin_idx: &usize,
@@ -124,24 +419,82 @@ impl ComponentManager {
         self.connect_pin_to_lump(&pins[*pin_idx], lump_id)
     }
 
-    fn get_pin_value(&self, pin_id: &usize) -> Bits {
+    pub fn try_get_gate_pins(
+        &self,
+        gate_id: &usize,
+        pin_type: &PinType,
+    ) -> Result<Vec<usize>, String> {
+        let gates = self.gates.read().unwrap();
+        let gate = gates
+            .get(gate_id)
+            .ok_or(format!("The gate with id {} doesn't exist", gate_id))?;
+        Ok(match &pin_type {
+            PinType::IN => gate.gpio.in_pins.clone(),
+            PinType::OUT => gate.gpio.out_pins.clone(),
+            PinType::INOUT => gate.gpio.inout_pins.clone(),
+        })
+    }
+
+    pub fn try_get_pin_value(&self, pin_id: &usize) -> Result<Bits, String> {
         self.pins
             .read()
             .unwrap()
             .get(pin_id)
-            .expect(format!("The pin with id {} doesn't exist", pin_id).as_str())
-            .value
-            .clone()
+            .map(|p| p.value.clone())
+            .ok_or(format!("The pin with id {} doesn't exist", pin_id))
     }
 
-    fn get_lump_value(&self, lump_id: &usize) -> Bits {
+    pub fn try_get_lump_value(&self, lump_id: &usize) -> Result<Bits, String> {
         self.lumps
             .read()
             .unwrap()
             .get(lump_id)
-            .expect(format!("The lump with id {} doesn't exist", lump_id).as_str())
-            .value
-            .clone()
+            .map(|l| l.value.clone())
+            .ok_or(format!("The lump with id {} doesn't exist", lump_id))
+    }
+
+    pub fn get_pin_value(&self, pin_id: &usize) -> Bits {
+        self.try_get_pin_value(pin_id)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn get_lump_value(&self, lump_id: &usize) -> Bits {
+        self.try_get_lump_value(lump_id)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Bits of a lump where equally strong drivers currently drive
+    /// opposite levels, which makes them X.
+    pub fn lump_conflicts(&self, lump_id: &usize) -> Result<Vec<usize>, String> {
+        self.lumps
+            .read()
+            .unwrap()
+            .get(lump_id)
+            .map(|l| l.conflicts.clone())
+            .ok_or(format!("The lump with id {} doesn't exist", lump_id))
+    }
+
+    pub fn current_time(&self) -> u64 {
+        self.current_sim_time
+    }
+
+    pub fn has_pending_events(&self) -> bool {
+        self.next_event_time().is_some()
+    }
+
+    /// Time of the earliest pending pin update or gate wakeup.
+    fn next_event_time(&self) -> Option<u64> {
+        let pin = self.pin_update_queue.read().unwrap().peek().map(|e| e.time);
+        let wakeup = self
+            .gate_wakeup_queue
+            .read()
+            .unwrap()
+            .peek()
+            .map(|e| e.time);
+        match (pin, wakeup) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
     }
 
     #[instrument(ret, skip(self))]
@@ -154,16 +507,27 @@ impl ComponentManager {
         let mut event_option = self.gate_update_queue.write().unwrap().pop_front();
         while let Some(event) = &event_option {
             info!("Updating Gate {}", event.target_gate_id);
-            self.gates
-                .read()
-                .unwrap()
-                .get(&event.target_gate_id)
-                .unwrap()
-                .handle_gate_event(event, self);
+            if let Some(gate) = self.gates.read().unwrap().get(&event.target_gate_id) {
+                gate.handle_gate_event(event, self);
+            }
             event_option = self.gate_update_queue.write().unwrap().pop_front();
         }
     }
 
+    /// Updates the gate again after `delay` even if none of its inputs
+    /// change, this is how self-timed components like clocks work.
+    #[instrument(skip(self))]
+    pub fn schedule_gate_wakeup(&self, delay: u64, gate_id: usize) {
+        self.gate_wakeup_queue
+            .write()
+            .unwrap()
+            .push(GateWakeupEvent {
+                time: self.current_sim_time + delay,
+                seq: self.next_event_seq(),
+                target_gate_id: gate_id,
+            });
+    }
+
     #[instrument(ret, skip(self))]
     fn schedule_lump_update(&self, event: LumpUpdateEvent) {
         self.lump_update_queue.write().unwrap().push_back(event);
@@ -173,23 +537,32 @@ impl ComponentManager {
     fn process_lump_events(&mut self) {
         let mut event_option = self.lump_update_queue.write().unwrap().pop_front();
         while let Some(event) = &event_option {
-            info!("Updating Gate {}", event.target_lump_id);
-            self.lumps
-                .write()
-                .unwrap()
-                .get_mut(&event.target_lump_id)
-                .unwrap()
-                .accept_update(event, self);
+            info!("Updating Lump {}", event.target_lump_id);
+            if let Some(lump) = self.lumps.write().unwrap().get_mut(&event.target_lump_id) {
+                lump.accept_update(event, self);
+            }
             event_option = self.lump_update_queue.write().unwrap().pop_front();
         }
     }
 
+    /// Sets a pin as if the lump it belongs to carried `value`.
     #[instrument(skip(self))]
-    fn schedule_pin_update(&self, delay: u64, id: usize, value: Bits) {
+    pub fn schedule_pin_update(&self, delay: u64, id: usize, value: Bits) {
+        self.push_pin_update(delay, id, value, false);
+    }
+
+    /// Sets the value a gate drives onto one of its output or inout pins.
+    fn schedule_pin_drive(&self, delay: u64, id: usize, value: Bits) {
+        self.push_pin_update(delay, id, value, true);
+    }
+
+    fn push_pin_update(&self, delay: u64, id: usize, value: Bits, drive: bool) {
         let pue = PinUpdateEvent {
             time: self.current_sim_time + delay,
+            seq: self.next_event_seq(),
             target_pin_id: id,
             value,
+            drive,
         };
         info!("Adding Pin Event at time {} for {}", pue.time, id);
         self.pin_update_queue.write().unwrap().push(pue);
@@ -197,41 +570,62 @@ impl ComponentManager {
     #[instrument(skip(self))]
     pub fn process_pin_events(&mut self) {
         info!("Start Processing Events at time {}", self.current_sim_time);
-        loop {
-            println!("{:?}", self.pin_update_queue.read().unwrap());
-            if let Some(event) = self.pin_update_queue.read().unwrap().peek() {
-                info!("Processing Event: {}", event.time);
-                // If not in same time step break
-                if self.current_sim_time < event.time {
-                    info!("Advancing time to {} and breaking Loop", event.time);
-                    self.current_sim_time = event.time;
-                    return;
-                }
-            } else {
-                // No Events
-                return;
+        while self.process_next_event() {}
+    }
+
+    /// Handles one pin event or gate wakeup of the current time step together
+    /// with all gate and lump updates it causes. Returns `false` if there is
+    /// no event left in this time step, after advancing the time to the next
+    /// pending event.
+    fn process_next_event(&mut self) -> bool {
+        debug!("{:?}", self.pin_update_queue.read().unwrap());
+        if let Some(time) = self.next_event_time() {
+            info!("Processing Event: {}", time);
+            // If not in same time step break
+            if self.current_sim_time < time {
+                info!("Advancing time to {} and breaking Loop", time);
+                self.current_sim_time = time;
+                return false;
             }
+        } else {
+            // No Events
+            return false;
+        }
+        let wakeup_due = self
+            .gate_wakeup_queue
+            .read()
+            .unwrap()
+            .peek()
+            .is_some_and(|e| e.time <= self.current_sim_time);
+        if wakeup_due {
+            let event = self.gate_wakeup_queue.write().unwrap().pop().unwrap();
+            self.schedule_gate_update(GateUpdateEvent {
+                sender_pin_id: None,
+                target_gate_id: event.target_gate_id,
+            });
+        } else {
             let event = self.pin_update_queue.write().unwrap().pop().unwrap();
             // Processing Event
-            self.pins
-                .write()
-                .unwrap()
-                .get_mut(&event.target_pin_id)
-                .unwrap()
-                .accept_update(&event.value, self);
-
-            self.process_gate_events();
-            self.process_lump_events();
+            if let Some(pin) = self.pins.write().unwrap().get_mut(&event.target_pin_id) {
+                pin.accept_update(&event.value, event.drive, self);
+            }
         }
+
+        self.process_gate_events();
+        self.process_lump_events();
+        self.flush_notifications();
+        true
     }
 }
 
-enum FlowDirection {
+#[allow(clippy::upper_case_acronyms)]
+pub enum FlowDirection {
     IN,
     OUT,
 }
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
-enum PinType {
+pub enum PinType {
     IN,
     OUT,
     INOUT,
@@ -241,44 +635,69 @@ pub struct Pin {
     id: usize,
     gate_id: usize,
     lump_id: Option<usize>,
+    /// The value the gate sees, for inout pins the level of the lump.
     value: Bits,
+    /// What the gate of an inout pin drives onto the lump.
+    driven: Bits,
     pin_type: PinType,
+    /// How hard the pin drives its lump, see `resolve_drivers`.
+    strength: Strength,
 }
 
 impl Pin {
-    fn new(id: usize, gate_id: usize, n: usize, pin_type: PinType) -> Self {
+    fn new(id: usize, gate_id: usize, n: usize, pin_type: PinType, strength: Strength) -> Self {
         Pin {
             id,
             gate_id,
             lump_id: None,
             value: Bits::new(n),
+            driven: Bits::new(n),
             pin_type,
+            strength,
         }
     }
     #[instrument(skip(cm))]
-    fn accept_update(&mut self, bits: &Bits, cm: &ComponentManager) {
+    fn accept_update(&mut self, bits: &Bits, drive: bool, cm: &ComponentManager) {
+        if let (PinType::INOUT, true) = (&self.pin_type, drive) {
+            if &self.driven != bits {
+                self.driven = bits.clone();
+                self.drive_lump(bits, cm);
+            }
+            return;
+        }
         if &self.value == bits {
             info!("Already same value");
             return;
         }
         self.value = bits.clone();
+        cm.notify(Notification::PinChanged {
+            pin_id: self.id,
+            time: cm.current_sim_time,
+            value: self.value.clone(),
+        });
         match self.pin_type {
             PinType::IN => cm.schedule_gate_update(GateUpdateEvent {
-                sender_pin_id: self.id,
+                sender_pin_id: Some(self.id),
                 target_gate_id: self.gate_id,
             }),
-            PinType::OUT => {
-                if let Some(lump_id) = self.lump_id {
-                    cm.schedule_lump_update(LumpUpdateEvent {
-                        sender_pin_id: self.id,
-                        target_lump_id: lump_id,
-                        bits: self.value.clone(),
-                    })
-                } else {
-                    info!("Pin {} is currently not connected", self.id);
-                }
-            }
-            PinType::INOUT => todo!(),
+            PinType::OUT => self.drive_lump(bits, cm),
+            PinType::INOUT => cm.schedule_gate_update(GateUpdateEvent {
+                sender_pin_id: Some(self.id),
+                target_gate_id: self.gate_id,
+            }),
+        }
+    }
+
+    fn drive_lump(&self, bits: &Bits, cm: &ComponentManager) {
+        if let Some(lump_id) = self.lump_id {
+            cm.schedule_lump_update(LumpUpdateEvent {
+                sender_pin_id: self.id,
+                target_lump_id: lump_id,
+                bits: bits.clone(),
+                strength: self.strength,
+            })
+        } else {
+            info!("Pin {} is currently not connected", self.id);
         }
     }
 
@@ -295,28 +714,73 @@ impl Pin {
 pub struct Lump {
     id: usize,
     pin_ids: Vec<usize>,
+    /// Latest value and strength of every output or inout pin that drove
+    /// the lump.
+    drivers: HashMap<usize, (Bits, Strength)>,
+    /// Bits where equally strong drivers disagree.
+    conflicts: Vec<usize>,
     value: Bits,
 }
 
 impl Lump {
-    fn new(id: usize, n: usize, cm: &ComponentManager) -> Self {
+    fn new(id: usize, n: usize) -> Self {
         Lump {
             id,
             pin_ids: Vec::new(),
+            drivers: HashMap::new(),
+            conflicts: Vec::new(),
             value: Bits::new(n),
         }
     }
 
+    /// Combines the values of all drivers, see `resolve_drivers`.
+    fn resolve(&mut self) -> Bits {
+        let width = self.value.len();
+        let drivers = self
+            .drivers
+            .values()
+            .filter(|(bits, _)| bits.len() == width)
+            .map(|(bits, strength)| (bits, *strength));
+        let (resolved, conflicts) = resolve_drivers(width, drivers);
+        if !conflicts.is_empty() {
+            warn!(
+                "Lump {} has conflicting drivers on bits {:?}",
+                self.id, conflicts
+            );
+        }
+        self.conflicts = conflicts;
+        resolved
+    }
+
     #[instrument(skip(self))]
     fn accept_update(&mut self, event: &LumpUpdateEvent, cm: &ComponentManager) {
-        if self.value == event.bits {
+        self.drivers
+            .insert(event.sender_pin_id, (event.bits.clone(), event.strength));
+        self.refresh(cm);
+    }
+
+    /// Resolves the drivers again and passes a changed value on to the
+    /// pins reading the lump.
+    fn refresh(&mut self, cm: &ComponentManager) {
+        let resolved = self.resolve();
+        if self.value == resolved {
             info!("Value are equal aborting update");
             return;
         }
-        self.value = event.bits.clone();
+        self.value = resolved;
+        cm.notify(Notification::LumpChanged {
+            lump_id: self.id,
+            time: cm.current_sim_time,
+            value: self.value.clone(),
+        });
+        // Inout pins, including the sender, see the resolved value too
+        let pins = cm.pins.read().unwrap();
         for pin_id in &self.pin_ids {
-            if pin_id != &event.sender_pin_id {
-                cm.schedule_pin_update(0, *pin_id, event.bits.clone());
+            let is_output = pins
+                .get(pin_id)
+                .is_some_and(|p| matches!(p.pin_type, PinType::OUT));
+            if !is_output {
+                cm.schedule_pin_update(0, *pin_id, self.value.clone());
             }
         }
     }
@@ -328,15 +792,27 @@ impl Lump {
     }
 
     fn disconnect(&mut self, pin_id: &usize) {
-        if let Ok(idx) = self.pin_ids.binary_search(pin_id) {
-            self.pin_ids.push(*pin_id);
-        }
+        self.pin_ids.retain(|id| id != pin_id);
+        self.drivers.remove(pin_id);
     }
 }
 
-pub struct GateUpdateData {
-    in_values: Vec<Bits>,
-    inout_values: Vec<(Bits, FlowDirection)>,
+pub struct GateUpdateData<'a> {
+    pub in_values: Vec<Bits>,
+    pub inout_values: Vec<(Bits, FlowDirection)>,
+    pub params: &'a Params,
+    /// Values the gate keeps between updates, e.g. stored bits or the time
+    /// of the next clock edge.
+    pub state: &'a mut Params,
+    pub time: u64,
+    wakeups: Vec<u64>,
+}
+
+impl GateUpdateData<'_> {
+    /// Requests another update of this gate after `delay`.
+    pub fn schedule_wakeup(&mut self, delay: u64) {
+        self.wakeups.push(delay);
+    }
 }
 
 #[derive(Debug)]
@@ -356,61 +832,103 @@ impl GPIOHandler {
             inout_pins: Vec::new(),
         }
     }
-    fn add_in(&mut self, n: usize, cm: &ComponentManager) {
-        let id = cm.create_pin(self.gate_id, n, PinType::IN);
+    pub fn add_in(&mut self, n: usize, cm: &ComponentManager) {
+        let id = cm.create_pin(self.gate_id, n, PinType::IN, Strength::HighZ);
         self.in_pins.push(id);
     }
-    fn add_out(&mut self, n: usize, cm: &ComponentManager) {
-        let id = cm.create_pin(self.gate_id, n, PinType::OUT);
-        self.out_pins.push(id);
+    pub fn add_out(&mut self, n: usize, cm: &ComponentManager) {
+        self.add_out_with_strength(n, Strength::Strong, cm);
+    }
+    pub fn add_in_out(&mut self, n: usize, cm: &ComponentManager) {
+        self.add_in_out_with_strength(n, Strength::Strong, cm);
     }
-    fn add_in_out(&mut self, n: usize, cm: &ComponentManager) {
-        let id = cm.create_pin(self.gate_id, n, PinType::INOUT);
+    /// An output driving with other than the usual `Strength::Strong`, e.g.
+    /// a pull resistor.
+    pub fn add_out_with_strength(&mut self, n: usize, strength: Strength, cm: &ComponentManager) {
+        let id = cm.create_pin(self.gate_id, n, PinType::OUT, strength);
         self.out_pins.push(id);
     }
+    pub fn add_in_out_with_strength(
+        &mut self,
+        n: usize,
+        strength: Strength,
+        cm: &ComponentManager,
+    ) {
+        let id = cm.create_pin(self.gate_id, n, PinType::INOUT, strength);
+        self.inout_pins.push(id);
+    }
+    fn all_pins(&self) -> Vec<usize> {
+        self.in_pins
+            .iter()
+            .chain(&self.out_pins)
+            .chain(&self.inout_pins)
+            .copied()
+            .collect()
+    }
     fn handle_gate_event(
         &self,
         event: &GateUpdateEvent,
-        logic_callback: &dyn Fn(&GateUpdateData, &dyn Fn(u64, usize, Bits)),
+        params: &Params,
+        state: &mut Params,
+        logic_callback: &LogicUpdaterFunc,
         cm: &ComponentManager,
     ) {
-        logic_callback(
-            &GateUpdateData {
-                in_values: self.in_pins.iter().map(|id| cm.get_pin_value(id)).collect(),
-                inout_values: self
-                    .inout_pins
-                    .iter()
-                    .map(|id| {
-                        (
-                            cm.get_pin_value(id),
-                            if id == &event.sender_pin_id {
-                                FlowDirection::IN
-                            } else {
-                                FlowDirection::OUT
-                            },
-                        )
-                    })
-                    .collect(),
-            },
-            &|delay, out_idx, value| {
-                let id = self.out_pins[out_idx];
-                cm.schedule_pin_update(delay, id, value);
-            },
-        )
+        let mut data = GateUpdateData {
+            in_values: self.in_pins.iter().map(|id| cm.get_pin_value(id)).collect(),
+            inout_values: self
+                .inout_pins
+                .iter()
+                .map(|id| {
+                    (
+                        cm.get_pin_value(id),
+                        if Some(*id) == event.sender_pin_id {
+                            FlowDirection::IN
+                        } else {
+                            FlowDirection::OUT
+                        },
+                    )
+                })
+                .collect(),
+            params,
+            state,
+            time: cm.current_sim_time,
+            wakeups: Vec::new(),
+        };
+        logic_callback(&mut data, &|delay, out_idx, value| {
+            let id = match self.out_pins.get(out_idx) {
+                Some(id) => *id,
+                None => self.inout_pins[out_idx - self.out_pins.len()],
+            };
+            cm.schedule_pin_drive(delay, id, value);
+        });
+        for delay in data.wakeups {
+            cm.schedule_gate_wakeup(delay, self.gate_id);
+        }
     }
 }
 
-type PinInitFunc = Box<dyn Fn(&mut GPIOHandler, &ComponentManager)>;
-type LogicUpdaterFunc = Box<dyn Fn(&GateUpdateData, &dyn Fn(u64, usize, Bits))>;
+type PinInitFunc = Box<dyn Fn(&mut GPIOHandler, &Params, &ComponentManager)>;
+/// Computes the outputs of a gate. The callback takes a delay, an output
+/// index and the value to drive; indices past the output pins address the
+/// inout pins.
+type LogicUpdaterFunc = Box<dyn Fn(&mut GateUpdateData, &dyn Fn(u64, usize, Bits))>;
 
 struct GateConstructor {
     init: PinInitFunc,
     update: Arc<LogicUpdaterFunc>,
+    /// Sources like clocks have no inputs that could trigger them, so they
+    /// get updated once as soon as they are added to the netlist.
+    initial_update: bool,
 }
 
 struct GenericGate {
     id: usize,
+    /// Name of the constructor this gate was built from
+    kind: String,
     gpio: GPIOHandler,
+    params: Params,
+    state: RwLock<Params>,
+    initial_update: bool,
     update_logic: Arc<LogicUpdaterFunc>,
 }
 
@@ -418,30 +936,49 @@ impl std::fmt::Debug for GenericGate {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("GenericGate")
             .field("id", &self.id)
+            .field("kind", &self.kind)
             .field("gpio", &self.gpio)
+            .field("params", &self.params)
+            .field("state", &self.state)
             .finish()
     }
 }
 
 impl GenericGate {
-    fn new(id: usize, con: &GateConstructor, cm: &ComponentManager) -> GenericGate {
+    fn new(
+        id: usize,
+        kind: &str,
+        con: &GateConstructor,
+        params: Params,
+        cm: &ComponentManager,
+    ) -> GenericGate {
         let mut gpio = GPIOHandler::new(id);
-        con.init.as_ref()(&mut gpio, cm);
+        con.init.as_ref()(&mut gpio, &params, cm);
         GenericGate {
             id,
+            kind: kind.to_string(),
             gpio,
+            params,
+            state: RwLock::new(Params::new()),
+            initial_update: con.initial_update,
             update_logic: con.update.clone(),
         }
     }
 
     fn handle_gate_event(&self, event: &GateUpdateEvent, cm: &ComponentManager) {
-        self.gpio
-            .handle_gate_event(event, self.update_logic.as_ref(), cm)
+        let mut state = self.state.write().unwrap();
+        self.gpio.handle_gate_event(
+            event,
+            &self.params,
+            &mut state,
+            self.update_logic.as_ref(),
+            cm,
+        )
     }
 }
 
 struct ComponentLibrary {
-    constructors: HashMap<&'static str, GateConstructor>,
+    constructors: HashMap<String, GateConstructor>,
 }
 
 impl std::fmt::Debug for ComponentLibrary {
@@ -454,7 +991,7 @@ impl ComponentLibrary {
     fn new() -> ComponentLibrary {
         let mut constructors = HashMap::new();
         let and: GateConstructor = GateConstructor {
-            init: Box::new(|gpio, cm| {
+            init: Box::new(|gpio, _, cm| {
                 gpio.add_in(1, cm);
                 gpio.add_in(1, cm);
                 gpio.add_out(1, cm);
@@ -462,13 +999,14 @@ impl ComponentLibrary {
             update: Arc::new(Box::new(|data, dispatch_output_update| {
                 let a = &data.in_values[0];
                 let b = &data.in_values[1];
-                dispatch_output_update(1, 0, a.and(&b));
+                dispatch_output_update(1, 0, a.and(b));
             })),
+            initial_update: false,
         };
         constructors.insert("and", and);
 
         let or: GateConstructor = GateConstructor {
-            init: Box::new(|gpio, cm| {
+            init: Box::new(|gpio, _, cm| {
                 gpio.add_in(1, cm);
                 gpio.add_in(1, cm);
                 gpio.add_out(1, cm);
@@ -476,13 +1014,14 @@ impl ComponentLibrary {
             update: Arc::new(Box::new(|data, dispatch_output_update| {
                 let a = &data.in_values[0];
                 let b = &data.in_values[1];
-                dispatch_output_update(1, 0, a.or(&b));
+                dispatch_output_update(1, 0, a.or(b));
             })),
+            initial_update: false,
         };
         constructors.insert("or", or);
 
         let not: GateConstructor = GateConstructor {
-            init: Box::new(|gpio, cm| {
+            init: Box::new(|gpio, _, cm| {
                 gpio.add_in(1, cm);
                 gpio.add_out(1, cm);
             }),
@@ -490,17 +1029,55 @@ impl ComponentLibrary {
                 let a = &data.in_values[0];
                 dispatch_output_update(1, 0, a.not());
             })),
+            initial_update: false,
         };
         constructors.insert("not", not);
 
+        let nand: GateConstructor = GateConstructor {
+            init: Box::new(|gpio, _, cm| {
+                gpio.add_in(1, cm);
+                gpio.add_in(1, cm);
+                gpio.add_out(1, cm);
+            }),
+            update: Arc::new(Box::new(|data, dispatch_output_update| {
+                let a = &data.in_values[0];
+                let b = &data.in_values[1];
+                dispatch_output_update(1, 0, a.nand(b));
+            })),
+            initial_update: false,
+        };
+        constructors.insert("nand", nand);
+
+        components::register_all(&mut constructors);
+
+        let constructors = constructors
+            .into_iter()
+            .map(|(name, constructor)| (name.to_string(), constructor))
+            .collect();
         ComponentLibrary { constructors }
     }
 
-    fn construct_gate(&self, name: &str, cm: &ComponentManager) -> Result<GenericGate, String> {
+    /// Adds a component defined at runtime, built-in names can't be reused.
+    fn register(&mut self, name: &str, constructor: GateConstructor) -> Result<(), String> {
+        if self.constructors.contains_key(name) {
+            return Err(format!("Gate with name {} already exists", name));
+        }
+        self.constructors.insert(name.to_string(), constructor);
+        Ok(())
+    }
+
+    fn construct_gate(
+        &self,
+        name: &str,
+        params: Params,
+        cm: &ComponentManager,
+    ) -> Result<GenericGate, String> {
         if self.constructors.contains_key(name) {
             Ok(GenericGate::new(
                 cm.get_id(),
+                name,
                 self.constructors.get(name).unwrap(),
+                params,
                 cm,
             ))
         } else {
@@ -0,0 +1,224 @@
+//! Reading memory images, e.g. to fill a `rom` or `ram` with a program.
+//!
+//! All loaders return one `Bits` of `width` bits per memory word, starting at
+//! address 0, and reject images reaching past a memory of `words` words.
+//! Words the image doesn't mention are X. Words wider than a byte are stored
+//! little endian in the byte based formats.
+
+use crate::bits::{Bits, LV};
+
+/// Largest image the loaders accept, in bytes.
+const MAX_IMAGE_BYTES: usize = 1 << 24;
+
+fn bytes_per_word(width: usize) -> usize {
+    width.div_ceil(8).max(1)
+}
+
+/// How many bytes the byte based formats may fill.
+fn max_bytes(width: usize, words: usize) -> usize {
+    words
+        .saturating_mul(bytes_per_word(width))
+        .min(MAX_IMAGE_BYTES)
+}
+
+/// Assembles words from bytes, missing bytes make their bits X. Set bits
+/// beyond `width` are an error.
+fn words_from_bytes(bytes: &[Option<u8>], width: usize) -> Result<Vec<Bits>, String> {
+    bytes
+        .chunks(bytes_per_word(width))
+        .enumerate()
+        .map(|(address, chunk)| {
+            let bit = |i: usize| {
+                chunk
+                    .get(i / 8)
+                    .copied()
+                    .flatten()
+                    .map(|b| b >> (i % 8) & 1)
+            };
+            if (width..chunk.len() * 8).any(|i| bit(i) == Some(1)) {
+                return Err(format!(
+                    "Word {:#x} of the image doesn't fit into {} bits",
+                    address, width
+                ));
+            }
+            Ok((0..width)
+                .map(|i| match bit(i) {
+                    Some(b) => LV::from(b == 1),
+                    None => LV::X,
+                })
+                .collect())
+        })
+        .collect()
+}
+
+/// Raw binary: consecutive words of whole bytes each.
+pub fn from_binary(bytes: &[u8], width: usize, words: usize) -> Result<Vec<Bits>, String> {
+    if bytes.len() > max_bytes(width, words) {
+        return Err(format!("The image of {} bytes is too large", bytes.len()));
+    }
+    let bytes: Vec<Option<u8>> = bytes.iter().map(|b| Some(*b)).collect();
+    words_from_bytes(&bytes, width)
+}
+
+fn hex_byte(record: &str, at: usize) -> Result<u8, String> {
+    record
+        .get(at..at + 2)
+        .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+        .ok_or(format!("Invalid hex digits in record {}", record))
+}
+
+/// Intel HEX with data, end of file and extended segment or linear address
+/// records. Checksums are verified.
+pub fn from_intel_hex(text: &str, width: usize, words: usize) -> Result<Vec<Bits>, String> {
+    let limit = max_bytes(width, words);
+    let mut bytes: Vec<Option<u8>> = Vec::new();
+    let mut base = 0;
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let record = line
+            .strip_prefix(':')
+            .ok_or(format!("Record {} doesn't start with ':'", line))?;
+        if record.len() % 2 != 0 || record.len() < 10 {
+            return Err(format!("Record {} has an invalid length", line));
+        }
+        let raw = (0..record.len() / 2)
+            .map(|i| hex_byte(record, 2 * i))
+            .collect::<Result<Vec<u8>, String>>()?;
+        if raw.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+            return Err(format!("Record {} has a wrong checksum", line));
+        }
+        let count = raw[0] as usize;
+        if raw.len() != count + 5 {
+            return Err(format!("Record {} has a wrong byte count", line));
+        }
+        let offset = (raw[1] as usize) << 8 | raw[2] as usize;
+        let data = &raw[4..4 + count];
+        match raw[3] {
+            0x00 => {
+                let start = base + offset;
+                if start + count > limit {
+                    return Err(format!("Address {:#x} is too large", start + count));
+                }
+                if bytes.len() < start + count {
+                    bytes.resize(start + count, None);
+                }
+                for (i, byte) in data.iter().enumerate() {
+                    bytes[start + i] = Some(*byte);
+                }
+            }
+            0x01 => break,
+            0x02 if count == 2 => base = ((data[0] as usize) << 8 | data[1] as usize) << 4,
+            0x04 if count == 2 => base = ((data[0] as usize) << 8 | data[1] as usize) << 16,
+            0x03 | 0x05 => {}
+            kind => return Err(format!("Unsupported record type {:02X} in {}", kind, line)),
+        }
+    }
+    words_from_bytes(&bytes, width)
+}
+
+/// Plain hex text: words as hex numbers separated by whitespace, `@` followed
+/// by a hex address continues at that address, `//` and `#` start comments.
+/// An `x` or `z` digit stands for four unknown or floating bits.
+pub fn from_hex_text(text: &str, width: usize, words: usize) -> Result<Vec<Bits>, String> {
+    let limit = words.min(MAX_IMAGE_BYTES);
+    let mut words: Vec<Bits> = Vec::new();
+    let mut address = 0;
+    for line in text.lines() {
+        let line = line.split("//").next().unwrap().split('#').next().unwrap();
+        for token in line.split_whitespace() {
+            if let Some(target) = token.strip_prefix('@') {
+                address = usize::from_str_radix(target, 16)
+                    .map_err(|_| format!("Invalid address {}", token))?;
+                continue;
+            }
+            if address >= limit {
+                return Err(format!("Address {:#x} is too large", address));
+            }
+            if words.len() <= address {
+                words.resize(address + 1, (0..width).map(|_| LV::X).collect());
+            }
+            words[address] = hex_word(token, width)?;
+            address += 1;
+        }
+    }
+    Ok(words)
+}
+
+fn hex_word(token: &str, width: usize) -> Result<Bits, String> {
+    let mut bits = Vec::new();
+    for c in token.chars().rev().filter(|c| *c != '_') {
+        let nibble = match c.to_ascii_lowercase() {
+            'x' => [LV::X, LV::X, LV::X, LV::X],
+            'z' => [LV::Z, LV::Z, LV::Z, LV::Z],
+            c => {
+                let digit = c
+                    .to_digit(16)
+                    .ok_or(format!("Invalid hex word {}", token))?;
+                [0, 1, 2, 3].map(|i| LV::from(digit >> i & 1 == 1))
+            }
+        };
+        bits.extend(nibble);
+    }
+    if bits.iter().skip(width).any(|lv| *lv != LV::L) {
+        return Err(format!(
+            "Hex word {} doesn't fit into {} bits",
+            token, width
+        ));
+    }
+    bits.resize(width, LV::L);
+    Ok(bits.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn show(words: &[Bits]) -> Vec<String> {
+        words
+            .iter()
+            .map(|w| (0..w.len()).rev().map(|i| w.get(i).to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_binary() {
+        let words = from_binary(&[0x34, 0x02, 0xff], 12, 16).unwrap();
+        assert_eq!(show(&words), ["001000110100", "XXXX11111111"]);
+        assert!(from_binary(&[0x34, 0x12], 12, 16).is_err());
+        assert!(from_binary(&[0x0f, 0x10], 4, 16).is_err());
+        assert!(from_binary(&[0x34, 0x02, 0xff], 12, 1).is_err());
+    }
+
+    #[test]
+    fn test_intel_hex() {
+        let text = ":0300020012AB340A\n:00000001FF\n";
+        let words = from_intel_hex(text, 8, 16).unwrap();
+        assert_eq!(
+            show(&words),
+            ["XXXXXXXX", "XXXXXXXX", "00010010", "10101011", "00110100"]
+        );
+        let words = from_intel_hex(text, 16, 16).unwrap();
+        assert_eq!(
+            show(&words),
+            ["XXXXXXXXXXXXXXXX", "1010101100010010", "XXXXXXXX00110100"]
+        );
+        assert!(from_intel_hex(":0300020012AB340B\n", 8, 16).is_err());
+        assert!(from_intel_hex(text, 4, 16).is_err());
+        assert!(from_intel_hex(text, 8, 4).is_err());
+        assert!(from_intel_hex("0300020012AB340A\n", 8, 16).is_err());
+    }
+
+    #[test]
+    fn test_hex_text() {
+        let text = "1f 0x // first words\n@4 a_5\n# the end\n";
+        let words = from_hex_text(text, 8, 16).unwrap();
+        assert_eq!(
+            show(&words),
+            ["00011111", "0000XXXX", "XXXXXXXX", "XXXXXXXX", "10100101"]
+        );
+        assert!(from_hex_text("1ff", 8, 16).is_err());
+        assert!(from_hex_text("g1", 8, 16).is_err());
+        assert!(from_hex_text(text, 8, 5).is_ok());
+        assert!(from_hex_text(text, 8, 4).is_err());
+        assert!(from_hex_text("@ffffff 1", 8, 16).is_err());
+    }
+}
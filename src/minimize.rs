@@ -0,0 +1,854 @@
+//! Two-level minimization: a cover of few and large cubes for a function
+//! given by the cubes where it is 1 and those where it doesn't matter.
+//!
+//! `Mode::Exact` finds all prime implicants with Quine–McCluskey and picks
+//! a cheapest cover of them by branch and bound, after taking essential
+//! primes and dropping dominated rows and columns of the prime table. It
+//! enumerates minterms, so it takes at most `EXACT_MAX_INPUTS` inputs, and
+//! falls back to the heuristic when the search exceeds `EXACT_EFFORT`. `Mode::Heuristic` follows
+//! Espresso and repeats expanding, dropping redundant and reducing cubes
+//! while the cover gets cheaper, without ever listing minterms. A cover
+//! costs its number of cubes first and its number of literals second.
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::truth_table::{Entry, TruthTable};
+
+pub const MAX_INPUTS: usize = 64;
+pub const EXACT_MAX_INPUTS: usize = 12;
+/// Work the exact cover search may do, in bit set words touched, before
+/// the heuristic takes over.
+pub const EXACT_EFFORT: usize = 20_000_000;
+/// Most inputs `Mode::Auto` minimizes exactly.
+pub const AUTO_EXACT_INPUTS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Exact up to `AUTO_EXACT_INPUTS` inputs, heuristic above.
+    Auto,
+    Exact,
+    Heuristic,
+}
+
+/// A product of literals: input `i` takes part if bit `i` of `care` is set
+/// and must then equal bit `i` of `value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cube {
+    care: u64,
+    value: u64,
+}
+
+fn mask(inputs: usize) -> u64 {
+    if inputs == 64 {
+        u64::MAX
+    } else {
+        (1 << inputs) - 1
+    }
+}
+
+impl Cube {
+    /// The cube without literals, which is always 1.
+    pub const UNIVERSE: Cube = Cube { care: 0, value: 0 };
+
+    pub fn new(care: u64, value: u64) -> Cube {
+        Cube {
+            care,
+            value: value & care,
+        }
+    }
+
+    /// The cube that is only 1 for the input combination `index`.
+    pub fn minterm(inputs: usize, index: u64) -> Cube {
+        Cube::new(mask(inputs), index)
+    }
+
+    /// Reads one character per input like a truth table row: `0`, `1` or
+    /// `-` if the input doesn't take part.
+    pub fn parse(pattern: &str) -> Result<Cube, String> {
+        if pattern.len() > MAX_INPUTS {
+            return Err(format!("A cube has at most {} inputs", MAX_INPUTS));
+        }
+        let mut cube = Cube::UNIVERSE;
+        for (i, c) in pattern.chars().enumerate() {
+            match c {
+                '0' => cube.care |= 1 << i,
+                '1' => cube = Cube::new(cube.care | 1 << i, cube.value | 1 << i),
+                '-' => {}
+                _ => return Err(format!("'{}' is not a cube entry", c)),
+            }
+        }
+        Ok(cube)
+    }
+
+    /// The pattern `parse` reads, for the first `inputs` inputs.
+    pub fn pattern(&self, inputs: usize) -> String {
+        (0..inputs)
+            .map(|i| match (self.care >> i & 1, self.value >> i & 1) {
+                (0, _) => '-',
+                (_, 0) => '0',
+                _ => '1',
+            })
+            .collect()
+    }
+
+    pub fn care(&self) -> u64 {
+        self.care
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// The inputs taking part with the level they need, lowest first.
+    pub fn literals(&self) -> impl Iterator<Item = (usize, bool)> + '_ {
+        (0..64)
+            .filter(|i| self.care >> i & 1 == 1)
+            .map(|i| (i, self.value >> i & 1 == 1))
+    }
+
+    pub fn literal_count(&self) -> usize {
+        self.care.count_ones() as usize
+    }
+
+    /// Whether every combination of `other` is in this cube.
+    pub fn contains(&self, other: &Cube) -> bool {
+        self.care & !other.care == 0 && (self.value ^ other.value) & self.care == 0
+    }
+
+    pub fn intersects(&self, other: &Cube) -> bool {
+        (self.value ^ other.value) & self.care & other.care == 0
+    }
+
+    /// This cube with the inputs of `other` removed, for cofactors.
+    fn without(&self, other: &Cube) -> Cube {
+        Cube::new(self.care & !other.care, self.value)
+    }
+
+    /// The intersection with a cube that shares no inputs.
+    fn and(&self, other: &Cube) -> Cube {
+        Cube::new(self.care | other.care, self.value | other.value)
+    }
+
+    fn literal(input: usize, level: bool) -> Cube {
+        Cube::new(1 << input, if level { 1 << input } else { 0 })
+    }
+}
+
+fn cost(cover: &[Cube]) -> (usize, usize) {
+    (cover.len(), cover.iter().map(Cube::literal_count).sum())
+}
+
+/// The cubes meeting `cube`, restricted to the other inputs.
+fn cofactor(cover: &[Cube], cube: &Cube) -> Vec<Cube> {
+    cover
+        .iter()
+        .filter(|c| c.intersects(cube))
+        .map(|c| c.without(cube))
+        .collect()
+}
+
+/// The input to split a cover on: one it uses in both polarities if there
+/// is one, and the most used otherwise.
+fn split_input(cover: &[Cube]) -> usize {
+    (0..64)
+        .max_by_key(|i| {
+            let ones = cover
+                .iter()
+                .filter(|c| (c.care & c.value) >> i & 1 == 1)
+                .count();
+            let zeros = cover
+                .iter()
+                .filter(|c| (c.care & !c.value) >> i & 1 == 1)
+                .count();
+            (ones > 0 && zeros > 0, ones + zeros)
+        })
+        .unwrap()
+}
+
+/// Drops duplicates and cubes inside others.
+fn absorb(mut cubes: Vec<Cube>) -> Vec<Cube> {
+    cubes.sort_by_key(Cube::literal_count);
+    let mut result: Vec<Cube> = Vec::new();
+    for cube in cubes {
+        if !result.iter().any(|r| r.contains(&cube)) {
+            result.push(cube);
+        }
+    }
+    result
+}
+
+fn is_tautology(cover: &[Cube]) -> bool {
+    if cover.iter().any(|c| c.care == 0) {
+        return true;
+    }
+    let ones = cover.iter().fold(0, |acc, c| acc | c.care & c.value);
+    let zeros = cover.iter().fold(0, |acc, c| acc | c.care & !c.value);
+    // Without the universal cube, a cover using each input in one polarity
+    // misses the combination with all inputs the other way
+    if ones & zeros == 0 {
+        return false;
+    }
+    let input = split_input(cover);
+    [false, true]
+        .iter()
+        .all(|level| is_tautology(&cofactor(cover, &Cube::literal(input, *level))))
+}
+
+/// Cubes covering exactly the combinations `cover` doesn't.
+fn complement(cover: &[Cube]) -> Vec<Cube> {
+    if cover.iter().any(|c| c.care == 0) {
+        return Vec::new();
+    }
+    match cover {
+        [] => vec![Cube::UNIVERSE],
+        [cube] => cube
+            .literals()
+            .map(|(i, level)| Cube::literal(i, !level))
+            .collect(),
+        _ => {
+            let input = split_input(cover);
+            let halves = [false, true].map(|level| {
+                let literal = Cube::literal(input, level);
+                complement(&cofactor(cover, &literal))
+            });
+            let mut result = Vec::new();
+            for (level, half) in halves.iter().enumerate() {
+                for cube in half {
+                    if level == 1 && halves[0].contains(cube) {
+                        // Already added without the input
+                        continue;
+                    }
+                    if halves[1 - level].contains(cube) {
+                        result.push(*cube);
+                    } else {
+                        result.push(cube.and(&Cube::literal(input, level == 1)));
+                    }
+                }
+            }
+            absorb(result)
+        }
+    }
+}
+
+/// Raises literals of every cube as long as it stays clear of `off`,
+/// dropping cubes that end up inside others.
+fn expand(cover: Vec<Cube>, off: &[Cube]) -> Vec<Cube> {
+    let mut cubes = cover;
+    // Large cubes first as they are the likeliest to swallow others
+    cubes.sort_by_key(Cube::literal_count);
+    let mut result: Vec<Cube> = Vec::new();
+    for mut cube in cubes {
+        if result.iter().any(|r| r.contains(&cube)) {
+            continue;
+        }
+        let literals: Vec<(usize, bool)> = cube.literals().collect();
+        for (i, _) in literals {
+            let raised = Cube::new(cube.care & !(1 << i), cube.value);
+            if !off.iter().any(|o| o.intersects(&raised)) {
+                cube = raised;
+            }
+        }
+        result.retain(|r| !cube.contains(r));
+        result.push(cube);
+    }
+    result
+}
+
+/// Drops cubes the others and `dont_care` cover, smallest first.
+fn irredundant(mut cover: Vec<Cube>, dont_care: &[Cube]) -> Vec<Cube> {
+    cover.sort_by_key(|c| std::cmp::Reverse(c.literal_count()));
+    let mut i = 0;
+    while i < cover.len() {
+        let rest: Vec<Cube> = cover
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, c)| *c)
+            .chain(dont_care.iter().copied())
+            .collect();
+        if is_tautology(&cofactor(&rest, &cover[i])) {
+            cover.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    cover
+}
+
+/// Shrinks every cube to the smallest one still covering what only it
+/// covers, so the next expansion can grow it another way.
+fn reduce(cover: &[Cube], dont_care: &[Cube]) -> Vec<Cube> {
+    let mut cover: Vec<Option<Cube>> = cover.iter().map(|c| Some(*c)).collect();
+    for i in 0..cover.len() {
+        let cube = cover[i].unwrap();
+        let rest: Vec<Cube> = cover
+            .iter()
+            .enumerate()
+            .filter_map(|(j, c)| c.filter(|_| j != i))
+            .chain(dont_care.iter().copied())
+            .collect();
+        let uncovered = complement(&cofactor(&rest, &cube));
+        cover[i] = match uncovered.split_first() {
+            None => None,
+            Some((first, others)) => {
+                // The supercube keeps the inputs all uncovered cubes agree on
+                let care = others
+                    .iter()
+                    .fold(first.care, |acc, c| acc & c.care & !(c.value ^ first.value));
+                Some(cube.and(&Cube::new(care, first.value)))
+            }
+        };
+    }
+    cover.into_iter().flatten().collect()
+}
+
+fn espresso(on: &[Cube], dont_care: &[Cube]) -> Vec<Cube> {
+    let off = complement(&[on, dont_care].concat());
+    let mut cover = irredundant(expand(on.to_vec(), &off), dont_care);
+    loop {
+        let candidate = irredundant(expand(reduce(&cover, dont_care), &off), dont_care);
+        if cost(&candidate) >= cost(&cover) {
+            return cover;
+        }
+        cover = candidate;
+    }
+}
+
+fn minterms(inputs: usize, cubes: &[Cube]) -> BTreeSet<u64> {
+    let mut result = BTreeSet::new();
+    for cube in cubes {
+        let free: Vec<usize> = (0..inputs).filter(|i| cube.care >> i & 1 == 0).collect();
+        for combination in 0..1u64 << free.len() {
+            let index = free
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| combination >> j & 1 == 1)
+                .fold(cube.value, |index, (_, i)| index | 1 << i);
+            result.insert(index);
+        }
+    }
+    result
+}
+
+/// Merges cubes differing in one literal until nothing merges anymore, the
+/// cubes that never merged are the primes.
+fn prime_implicants(inputs: usize, minterms: &BTreeSet<u64>) -> Vec<Cube> {
+    let mut level: HashSet<Cube> = minterms.iter().map(|m| Cube::minterm(inputs, *m)).collect();
+    let mut primes = Vec::new();
+    while !level.is_empty() {
+        let mut next = HashSet::new();
+        let mut merged = HashSet::new();
+        for cube in &level {
+            for (i, _) in cube.literals() {
+                let neighbour = Cube::new(cube.care, cube.value ^ 1 << i);
+                if level.contains(&neighbour) {
+                    next.insert(Cube::new(cube.care & !(1 << i), cube.value));
+                    merged.insert(*cube);
+                }
+            }
+        }
+        primes.extend(level.iter().filter(|c| !merged.contains(c)));
+        level = next;
+    }
+    primes.sort();
+    primes
+}
+
+fn ones(set: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    set.iter().enumerate().flat_map(|(w, word)| {
+        (0..64)
+            .filter(move |b| word >> b & 1 == 1)
+            .map(move |b| w * 64 + b)
+    })
+}
+
+fn is_subset(a: &[u64], b: &[u64], within: &[u64]) -> bool {
+    a.iter()
+        .zip(b)
+        .zip(within)
+        .all(|((a, b), within)| a & within & !b == 0)
+}
+
+fn without(set: &[u64], other: &[u64]) -> Vec<u64> {
+    set.iter().zip(other).map(|(s, o)| s & !o).collect()
+}
+
+fn has(set: &[u64], i: usize) -> bool {
+    set[i / 64] >> (i % 64) & 1 == 1
+}
+
+fn clear(set: &mut [u64], i: usize) {
+    set[i / 64] &= !(1 << (i % 64));
+}
+
+/// The search ran out of `EXACT_EFFORT`.
+struct OutOfEffort;
+
+/// Branch and bound over the prime table: rows are the minterms to cover,
+/// columns the primes, both kept as bit sets.
+struct CoverSearch {
+    literals: Vec<usize>,
+    /// The rows each column covers.
+    col_rows: Vec<Vec<u64>>,
+    /// The columns covering each row.
+    row_cols: Vec<Vec<u64>>,
+    best: Option<(Vec<usize>, (usize, usize))>,
+    effort: usize,
+}
+
+impl CoverSearch {
+    fn spend(&mut self, work: usize) -> Result<(), OutOfEffort> {
+        self.effort = self.effort.checked_sub(work).ok_or(OutOfEffort)?;
+        Ok(())
+    }
+
+    fn cost(&self, chosen: &[usize]) -> (usize, usize) {
+        (chosen.len(), chosen.iter().map(|c| self.literals[*c]).sum())
+    }
+
+    fn choose(&self, c: usize, rows: &mut Vec<u64>, cols: &mut [u64], chosen: &mut Vec<usize>) {
+        chosen.push(c);
+        *rows = without(rows, &self.col_rows[c]);
+        clear(cols, c);
+    }
+
+    /// Takes essential columns and drops dominated rows and columns until
+    /// nothing changes. False if some row can't be covered anymore.
+    fn reduce(
+        &mut self,
+        rows: &mut Vec<u64>,
+        cols: &mut [u64],
+        chosen: &mut Vec<usize>,
+    ) -> Result<bool, OutOfEffort> {
+        loop {
+            let row_list: Vec<usize> = ones(rows).collect();
+            let col_list: Vec<usize> = ones(cols).collect();
+            // Comparing all pairs of rows and all pairs of columns
+            self.spend(row_list.len().pow(2) * cols.len() + col_list.len().pow(2) * rows.len())?;
+            let mut changed = false;
+
+            for r in &row_list {
+                if !has(rows, *r) {
+                    continue;
+                }
+                let mut covering = ones(&self.row_cols[*r]).filter(|c| has(cols, *c));
+                match (covering.next(), covering.next()) {
+                    (None, _) => return Ok(false),
+                    (Some(c), None) => {
+                        self.choose(c, rows, cols, chosen);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            if changed {
+                continue;
+            }
+
+            // A row covered by every column covering another row is
+            // covered along with that row
+            for (i, a) in row_list.iter().enumerate() {
+                for (j, b) in row_list.iter().enumerate() {
+                    let removed = |r: &usize| !has(rows, *r);
+                    if i == j || removed(a) || removed(b) {
+                        continue;
+                    }
+                    let (cols_a, cols_b) = (&self.row_cols[*a], &self.row_cols[*b]);
+                    if is_subset(cols_a, cols_b, cols)
+                        && (j > i || !is_subset(cols_b, cols_a, cols))
+                    {
+                        clear(rows, *b);
+                        changed = true;
+                    }
+                }
+            }
+
+            // A column covering no more than a cheaper or equal one is never
+            // needed
+            for (i, a) in col_list.iter().enumerate() {
+                for (j, b) in col_list.iter().enumerate() {
+                    let removed = |c: &usize| !has(cols, *c);
+                    if i == j || removed(a) || removed(b) || self.literals[*b] > self.literals[*a] {
+                        continue;
+                    }
+                    let (rows_a, rows_b) = (&self.col_rows[*a], &self.col_rows[*b]);
+                    let tie =
+                        self.literals[*a] == self.literals[*b] && is_subset(rows_b, rows_a, rows);
+                    if is_subset(rows_a, rows_b, rows) && (!tie || j < i) {
+                        clear(cols, *a);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Rows no two of which share a column each need a column of their own,
+    /// which bounds the cost of covering `rows` from below.
+    fn lower_bound(&self, rows: &[u64], cols: &[u64]) -> (usize, usize) {
+        let mut row_list: Vec<usize> = ones(rows).collect();
+        row_list.sort_by_key(|r| ones(&self.row_cols[*r]).filter(|c| has(cols, *c)).count());
+        let mut used = vec![0u64; cols.len()];
+        let (mut count, mut literals) = (0, 0);
+        for r in row_list {
+            let covering: Vec<u64> = self.row_cols[r]
+                .iter()
+                .zip(cols)
+                .map(|(a, b)| a & b)
+                .collect();
+            if covering.iter().zip(&used).all(|(a, b)| a & b == 0) {
+                count += 1;
+                literals += ones(&covering).map(|c| self.literals[c]).min().unwrap_or(0);
+                for (u, c) in used.iter_mut().zip(&covering) {
+                    *u |= c;
+                }
+            }
+        }
+        (count, literals)
+    }
+
+    fn search(
+        &mut self,
+        mut rows: Vec<u64>,
+        mut cols: Vec<u64>,
+        mut chosen: Vec<usize>,
+    ) -> Result<(), OutOfEffort> {
+        self.spend(1)?;
+        if !self.reduce(&mut rows, &mut cols, &mut chosen)? {
+            return Ok(());
+        }
+        let cost = self.cost(&chosen);
+        if rows.iter().all(|w| *w == 0) {
+            if self.best.as_ref().is_none_or(|(_, best)| cost < *best) {
+                self.best = Some((chosen, cost));
+            }
+            return Ok(());
+        }
+        let (count, literals) = self.lower_bound(&rows, &cols);
+        if let Some((_, best)) = &self.best {
+            if (cost.0 + count, cost.1 + literals) >= *best {
+                return Ok(());
+            }
+        }
+        // Branch on the columns of the row with the fewest, leaving out the
+        // ones earlier branches took
+        let covering = |r: usize| -> Vec<usize> {
+            ones(&self.row_cols[r]).filter(|c| has(&cols, *c)).collect()
+        };
+        let row = ones(&rows).min_by_key(|r| covering(*r).len()).unwrap();
+        let mut options = covering(row);
+        options.sort_by_key(|c| {
+            let covered = ones(&self.col_rows[*c]).filter(|r| has(&rows, *r)).count();
+            (std::cmp::Reverse(covered), self.literals[*c])
+        });
+        for c in options {
+            let (mut rows, mut next_cols, mut chosen) =
+                (rows.clone(), cols.clone(), chosen.clone());
+            self.choose(c, &mut rows, &mut next_cols, &mut chosen);
+            self.search(rows, next_cols, chosen)?;
+            clear(&mut cols, c);
+        }
+        Ok(())
+    }
+}
+
+/// The cheapest cover of the primes, or the best one found before running
+/// out of `EXACT_EFFORT` if there is one.
+fn quine_mccluskey(
+    inputs: usize,
+    on: &[Cube],
+    dont_care: &[Cube],
+) -> Result<Vec<Cube>, Option<Vec<Cube>>> {
+    let on = minterms(inputs, on);
+    let all = on.union(&minterms(inputs, dont_care)).copied().collect();
+    let primes = prime_implicants(inputs, &all);
+    let minterms: Vec<Cube> = on.iter().map(|m| Cube::minterm(inputs, *m)).collect();
+    let set = |len: usize, members: &dyn Fn(usize) -> bool| -> Vec<u64> {
+        let mut set = vec![0; len.div_ceil(64)];
+        for i in (0..len).filter(|i| members(*i)) {
+            set[i / 64] |= 1 << (i % 64);
+        }
+        set
+    };
+    let mut search = CoverSearch {
+        literals: primes.iter().map(Cube::literal_count).collect(),
+        col_rows: primes
+            .iter()
+            .map(|p| set(minterms.len(), &|r| p.contains(&minterms[r])))
+            .collect(),
+        row_cols: minterms
+            .iter()
+            .map(|m| set(primes.len(), &|c| primes[c].contains(m)))
+            .collect(),
+        best: None,
+        effort: EXACT_EFFORT,
+    };
+    let rows = set(minterms.len(), &|_| true);
+    let cols = set(primes.len(), &|_| true);
+    let finished = search.search(rows, cols, Vec::new()).is_ok();
+    let best = search
+        .best
+        .map(|(chosen, _)| chosen.iter().map(|c| primes[*c]).collect());
+    match (finished, best) {
+        (true, Some(best)) => Ok(best),
+        (_, best) => Err(best),
+    }
+}
+
+/// A cheap cover of the function that is 1 in the `on` cubes, may be
+/// anything in the `dont_care` cubes and is 0 elsewhere, sorted.
+pub fn minimize(
+    inputs: usize,
+    on: &[Cube],
+    dont_care: &[Cube],
+    mode: Mode,
+) -> Result<Vec<Cube>, String> {
+    if inputs > MAX_INPUTS {
+        return Err(format!("Minimization takes at most {} inputs", MAX_INPUTS));
+    }
+    if on
+        .iter()
+        .chain(dont_care)
+        .any(|c| c.care & !mask(inputs) != 0)
+    {
+        return Err(format!("A cube uses more than {} inputs", inputs));
+    }
+    let exact = match mode {
+        Mode::Auto => inputs <= AUTO_EXACT_INPUTS,
+        Mode::Exact if inputs > EXACT_MAX_INPUTS => {
+            return Err(format!(
+                "Exact minimization takes at most {} inputs",
+                EXACT_MAX_INPUTS
+            ))
+        }
+        Mode::Exact => true,
+        Mode::Heuristic => false,
+    };
+    let mut cover = if on.is_empty() {
+        Vec::new()
+    } else if exact {
+        match quine_mccluskey(inputs, on, dont_care) {
+            Ok(cover) => cover,
+            Err(partial) => {
+                let heuristic = espresso(on, dont_care);
+                match partial {
+                    Some(cover) if cost(&cover) < cost(&heuristic) => cover,
+                    _ => heuristic,
+                }
+            }
+        }
+    } else {
+        espresso(on, dont_care)
+    };
+    cover.sort();
+    Ok(cover)
+}
+
+/// A cover for every output of `table`. Outputs that are X, don't care or
+/// not listed may come out either way.
+pub fn minimize_table(table: &TruthTable, mode: Mode) -> Result<Vec<Vec<Cube>>, String> {
+    // Rejects contradicting rows
+    table.expand()?;
+    let rows: Vec<Cube> = table
+        .rows()
+        .iter()
+        .map(|row| {
+            row.inputs
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| **e != Entry::DontCare)
+                .fold(Cube::UNIVERSE, |cube, (i, e)| {
+                    cube.and(&Cube::literal(i, *e == Entry::High))
+                })
+        })
+        .collect();
+    let unlisted = complement(&rows);
+    (0..table.outputs().len())
+        .map(|out| {
+            let entries = || {
+                rows.iter()
+                    .zip(table.rows())
+                    .map(move |(c, r)| (*c, r.outputs[out]))
+            };
+            let on: Vec<Cube> = entries()
+                .filter(|(_, e)| *e == Entry::High)
+                .map(|(c, _)| c)
+                .collect();
+            let dont_care: Vec<Cube> = entries()
+                .filter(|(_, e)| matches!(e, Entry::Unknown | Entry::DontCare))
+                .map(|(c, _)| c)
+                .chain(unlisted.iter().copied())
+                .collect();
+            minimize(table.inputs().len(), &on, &dont_care, mode)
+        })
+        .collect()
+}
+
+/// `cover` as an expression on inputs called `names`, e.g. `a & ~b | c`.
+pub fn sum_of_products(names: &[String], cover: &[Cube]) -> String {
+    if cover.is_empty() {
+        return "1'b0".to_string();
+    }
+    cover
+        .iter()
+        .map(|cube| match cube.literal_count() {
+            0 => "1'b1".to_string(),
+            _ => cube
+                .literals()
+                .map(|(i, level)| format!("{}{}", if level { "" } else { "~" }, names[i]))
+                .collect::<Vec<_>>()
+                .join(" & "),
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// Minimized equations for `table` in the syntax `Equations::parse`
+/// reads, to register as a component or build from gates.
+pub fn table_equations(table: &TruthTable, mode: Mode) -> Result<String, String> {
+    let covers = minimize_table(table, mode)?;
+    let mut text = String::new();
+    if !table.inputs().is_empty() {
+        text += &format!("input {};\n", table.inputs().join(", "));
+    }
+    text += &format!("output {};\n", table.outputs().join(", "));
+    for (name, cover) in table.outputs().iter().zip(&covers) {
+        text += &format!("{} = {};\n", name, sum_of_products(table.inputs(), cover));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bits::Bits, expr::Equations};
+
+    fn cubes(patterns: &[&str]) -> Vec<Cube> {
+        patterns.iter().map(|p| Cube::parse(p).unwrap()).collect()
+    }
+
+    fn patterns(inputs: usize, cover: &[Cube]) -> Vec<String> {
+        cover.iter().map(|c| c.pattern(inputs)).collect()
+    }
+
+    /// Whether `cover` is 1 on `on`, 0 off `on` and `dont_care`.
+    fn check(inputs: usize, cover: &[Cube], on: &[Cube], dont_care: &[Cube]) {
+        let on = minterms(inputs, on);
+        let dont_care = minterms(inputs, dont_care);
+        let covered = minterms(inputs, cover);
+        for m in 0..1u64 << inputs {
+            if on.contains(&m) {
+                assert!(covered.contains(&m), "{:b} is missing", m);
+            } else if !dont_care.contains(&m) {
+                assert!(!covered.contains(&m), "{:b} is covered", m);
+            }
+        }
+    }
+
+    /// A pseudo random function for comparing the modes.
+    fn random_function(inputs: usize, seed: u64) -> (Vec<Cube>, Vec<Cube>) {
+        let mut state = seed;
+        let (mut on, mut dont_care) = (Vec::new(), Vec::new());
+        for m in 0..1u64 << inputs {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            match state >> 60 {
+                0..=6 => on.push(Cube::minterm(inputs, m)),
+                7..=8 => dont_care.push(Cube::minterm(inputs, m)),
+                _ => {}
+            }
+        }
+        (on, dont_care)
+    }
+
+    #[test]
+    fn test_cubes() {
+        let cube = Cube::parse("1-0").unwrap();
+        assert_eq!(cube.pattern(4), "1-0-");
+        assert_eq!(cube.literals().collect::<Vec<_>>(), [(0, true), (2, false)]);
+        assert!(cube.contains(&Cube::parse("110").unwrap()));
+        assert!(!cube.intersects(&Cube::parse("0").unwrap()));
+        assert!(Cube::parse("1x").is_err());
+
+        let cover = cubes(&["1-", "-1"]);
+        assert_eq!(patterns(2, &complement(&cover)), ["00"]);
+        assert!(!is_tautology(&cover));
+        assert!(is_tautology(&cubes(&["1-", "01", "-0"])));
+        assert_eq!(complement(&[]), [Cube::UNIVERSE]);
+    }
+
+    #[test]
+    fn test_textbook_function() {
+        // f = Σm(4, 8, 10, 11, 12, 15) + d(9, 14), input 0 most significant
+        let minterm =
+            |m: u64| Cube::minterm(4, (0..4).fold(0, |acc, i| acc | (m >> (3 - i) & 1) << i));
+        let on: Vec<Cube> = [4, 8, 10, 11, 12, 15].map(minterm).to_vec();
+        let dont_care: Vec<Cube> = [9, 14].map(minterm).to_vec();
+        for mode in [Mode::Exact, Mode::Heuristic] {
+            let cover = minimize(4, &on, &dont_care, mode).unwrap();
+            check(4, &cover, &on, &dont_care);
+            // b~c~d + a~b + ac
+            assert_eq!(cost(&cover), (3, 7), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn test_modes_agree() {
+        for (inputs, seed) in [(4, 1), (5, 2), (6, 3), (7, 4)] {
+            let (on, dont_care) = random_function(inputs, seed);
+            let exact = minimize(inputs, &on, &dont_care, Mode::Exact).unwrap();
+            let heuristic = minimize(inputs, &on, &dont_care, Mode::Heuristic).unwrap();
+            check(inputs, &exact, &on, &dont_care);
+            check(inputs, &heuristic, &on, &dont_care);
+            assert!(cost(&exact) <= cost(&heuristic));
+        }
+        for seed in [5, 6] {
+            let (on, dont_care) = random_function(8, seed);
+            let auto = minimize(8, &on, &dont_care, Mode::Auto).unwrap();
+            check(8, &auto, &on, &dont_care);
+            let heuristic = minimize(8, &on, &dont_care, Mode::Heuristic).unwrap();
+            assert!(cost(&auto) <= cost(&heuristic));
+        }
+        // Too large to list the minterms
+        let on = cubes(&[&"1".repeat(40), &format!("{}0", "-".repeat(39))]);
+        let cover = minimize(40, &on, &[], Mode::Auto).unwrap();
+        assert_eq!(cover.len(), 2);
+        assert!(minimize(40, &on, &[], Mode::Exact).is_err());
+        assert!(minimize(3, &on, &[], Mode::Auto).is_err());
+    }
+
+    #[test]
+    fn test_table() {
+        let mut table = TruthTable::new(&["a", "b", "c"], &["y", "z"]).unwrap();
+        table.add_row("11-", "10").unwrap();
+        table.add_row("0-1", "1-").unwrap();
+        table.add_row("000", "00").unwrap();
+        table.add_row("100", "X1").unwrap();
+        // 010 and 101 aren't listed, y can be a | c or b | c
+        let covers = minimize_table(&table, Mode::Auto).unwrap();
+        assert_eq!(cost(&covers[0]), (2, 2));
+        assert_eq!(sum_of_products(table.inputs(), &covers[1]), "a & ~b");
+
+        let text = table_equations(&table, Mode::Heuristic).unwrap();
+        assert!(text.starts_with("input a, b, c;\noutput y, z;\n"));
+        assert!(text.ends_with("z = a & ~b;\n"));
+        let equations = Equations::parse(&text).unwrap();
+        for (index, row) in table.expand().unwrap().iter().enumerate() {
+            let inputs: Vec<Bits> = (0..3)
+                .map(|i| Bits::from_u64(1, index as u64 >> i & 1).unwrap())
+                .collect();
+            for (value, entry) in equations.evaluate(&inputs).iter().zip(row) {
+                if matches!(entry, Entry::Low | Entry::High) {
+                    assert_eq!(value.get(0), entry.to_lv());
+                }
+            }
+        }
+        let empty = TruthTable::new(&["a"], &["y"]).unwrap();
+        let covers = minimize_table(&empty, Mode::Exact).unwrap();
+        assert_eq!(sum_of_products(empty.inputs(), &covers[0]), "1'b0");
+    }
+}
@@ -0,0 +1,203 @@
+use std::rc::Rc;
+
+use crate::{bits::Bits, history::Edit, ComponentManager};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    PinChanged {
+        pin_id: usize,
+        time: u64,
+        value: Bits,
+    },
+    LumpChanged {
+        lump_id: usize,
+        time: u64,
+        value: Bits,
+    },
+    Topology(Edit),
+}
+
+/// Selects which notifications a subscription receives.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Watch {
+    Pin(usize),
+    Lump(usize),
+    Topology,
+    All,
+}
+
+impl Watch {
+    fn matches(&self, notification: &Notification) -> bool {
+        match (self, notification) {
+            (Watch::All, _) => true,
+            (Watch::Pin(id), Notification::PinChanged { pin_id, .. }) => id == pin_id,
+            (Watch::Lump(id), Notification::LumpChanged { lump_id, .. }) => id == lump_id,
+            (Watch::Topology, Notification::Topology(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+type NotifyCallback = Rc<dyn Fn(&Notification)>;
+
+struct Subscription {
+    id: usize,
+    watch: Watch,
+    /// Subscriptions without a callback collect into the drainable queue.
+    callback: Option<NotifyCallback>,
+}
+
+#[derive(Default)]
+pub(crate) struct Observers {
+    next_id: usize,
+    subscriptions: Vec<Subscription>,
+    pending: Vec<Notification>,
+    queue: Vec<Notification>,
+}
+
+impl std::fmt::Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Observers")
+            .field("subscriptions", &self.subscriptions.len())
+            .field("pending", &self.pending.len())
+            .field("queue", &self.queue.len())
+            .finish()
+    }
+}
+
+impl Observers {
+    fn add(&mut self, watch: Watch, callback: Option<NotifyCallback>) -> usize {
+        self.next_id += 1;
+        self.subscriptions.push(Subscription {
+            id: self.next_id,
+            watch,
+            callback,
+        });
+        self.next_id
+    }
+
+    fn wants(&self, notification: &Notification) -> bool {
+        self.subscriptions
+            .iter()
+            .any(|s| s.watch.matches(notification))
+    }
+}
+
+impl ComponentManager {
+    /// Calls `callback` for every matching change. Callbacks run after the
+    /// current event or edit has been fully applied, so they may read values
+    /// from the manager but must not subscribe or unsubscribe themselves.
+    pub fn subscribe(&self, watch: Watch, callback: impl Fn(&Notification) + 'static) -> usize {
+        self.observers
+            .write()
+            .unwrap()
+            .add(watch, Some(Rc::new(callback)))
+    }
+
+    /// Like `subscribe` but matching changes are collected until
+    /// `drain_notifications` is called.
+    pub fn watch(&self, watch: Watch) -> usize {
+        self.observers.write().unwrap().add(watch, None)
+    }
+
+    pub fn unsubscribe(&self, subscription_id: usize) -> bool {
+        let mut observers = self.observers.write().unwrap();
+        let before = observers.subscriptions.len();
+        observers.subscriptions.retain(|s| s.id != subscription_id);
+        before != observers.subscriptions.len()
+    }
+
+    pub fn drain_notifications(&self) -> Vec<Notification> {
+        std::mem::take(&mut self.observers.write().unwrap().queue)
+    }
+
+    pub(crate) fn notify(&self, notification: Notification) {
+        let mut observers = self.observers.write().unwrap();
+        if observers.wants(&notification) {
+            observers.pending.push(notification);
+        }
+    }
+
+    /// Hands pending notifications to their subscribers. Must be called while
+    /// no pin, lump or gate lock is held.
+    pub(crate) fn flush_notifications(&self) {
+        let mut observers = self.observers.write().unwrap();
+        if observers.pending.is_empty() {
+            return;
+        }
+        let mut calls = Vec::new();
+        for notification in std::mem::take(&mut observers.pending) {
+            let mut queued = false;
+            for s in &observers.subscriptions {
+                if !s.watch.matches(&notification) {
+                    continue;
+                }
+                match &s.callback {
+                    Some(callback) => calls.push((callback.clone(), notification.clone())),
+                    None => queued = true,
+                }
+            }
+            if queued {
+                observers.queue.push(notification);
+            }
+        }
+        drop(observers);
+        for (callback, notification) in calls {
+            callback(&notification);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{Notification, Watch};
+    use crate::{bits::Bits, history::Edit, ComponentManager, PinType};
+
+    #[test]
+    fn test_value_callbacks() {
+        let mut cm = ComponentManager::new();
+        let not = cm.create_gate("not").unwrap();
+        let lump = cm.create_lump(1);
+        cm.connect_gate_pin_to_lump(&not, &0, &PinType::OUT, &lump)
+            .unwrap();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let sink = seen.clone();
+        cm.subscribe(Watch::Lump(lump), move |n| {
+            sink.borrow_mut().push(n.clone())
+        });
+
+        let input = cm.get_gate_pins(&not, &PinType::IN)[0];
+        cm.schedule_pin_update(0, input, Bits::new(1).set_num(0));
+        cm.process_pin_events();
+        cm.process_pin_events();
+
+        assert_eq!(
+            seen.borrow().as_slice(),
+            &[Notification::LumpChanged {
+                lump_id: lump,
+                time: 1,
+                value: Bits::new(1).set_num(1),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_topology_queue() {
+        let mut cm = ComponentManager::new();
+        let id = cm.watch(Watch::Topology);
+        let lump = cm.create_lump(4);
+        cm.undo().unwrap();
+        assert_eq!(
+            cm.drain_notifications(),
+            vec![
+                Notification::Topology(Edit::CreateLump { lump_id: lump }),
+                Notification::Topology(Edit::DeleteLump { lump_id: lump }),
+            ]
+        );
+        assert!(cm.unsubscribe(id));
+        cm.create_lump(4);
+        assert!(cm.drain_notifications().is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::bits::Bits;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    Int(i64),
+    Bool(bool),
+    Text(String),
+    Bits(Bits),
+    /// A list of values, e.g. the contents of a memory.
+    Words(Vec<Bits>),
+}
+
+/// Named parameters of a gate instance, e.g. bit widths or delays.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params {
+    values: HashMap<String, Param>,
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Params {
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn with(mut self, name: &str, value: Param) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Param> {
+        self.values.get(name)
+    }
+
+    /// Sets a parameter and returns the value it replaced.
+    pub fn set(&mut self, name: &str, value: Param) -> Option<Param> {
+        self.values.insert(name.to_string(), value)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Param> {
+        self.values.remove(name)
+    }
+
+    pub fn get_int(&self, name: &str, default: i64) -> i64 {
+        match self.values.get(name) {
+            Some(Param::Int(v)) => *v,
+            _ => default,
+        }
+    }
+
+    /// Reads a value given either as `Bits` or as a number, sized to `width`.
+    pub fn get_bits(&self, name: &str, width: usize) -> Option<Bits> {
+        match self.values.get(name) {
+            Some(Param::Bits(b)) if b.len() == width => Some(b.clone()),
+            Some(Param::Int(v)) => Some(Bits::new(width).set_num(*v)),
+            _ => None,
+        }
+    }
+
+    pub fn get_words(&self, name: &str) -> Option<&Vec<Bits>> {
+        match self.values.get(name) {
+            Some(Param::Words(words)) => Some(words),
+            _ => None,
+        }
+    }
+
+    pub fn get_bool(&self, name: &str, default: bool) -> bool {
+        match self.values.get(name) {
+            Some(Param::Bool(v)) => *v,
+            _ => default,
+        }
+    }
+}
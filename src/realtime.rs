@@ -0,0 +1,145 @@
+//! Pacing simulation time against the wall clock, so clocks and LEDs change
+//! at a visible rate instead of all at once.
+
+use crate::{
+    run::{now_micros, RunBudget, RunStatus},
+    ComponentManager,
+};
+
+/// Maps wall clock time onto simulation time at an adjustable speed.
+///
+/// The pacer is anchored at a pair of wall and simulation times; each
+/// `advance` runs the simulation up to the time that corresponds to the
+/// current wall time. Pausing and speed changes re-anchor, so simulation time
+/// never jumps.
+#[derive(Debug, Clone)]
+pub struct RealTimePacer {
+    sim_units_per_second: f64,
+    running: bool,
+    anchor_wall_micros: u64,
+    anchor_sim_time: u64,
+}
+
+impl RealTimePacer {
+    /// Creates a paused pacer. `sim_units_per_second` of 1000.0 makes one
+    /// simulation time unit last one millisecond.
+    pub fn new(sim_units_per_second: f64) -> Self {
+        RealTimePacer {
+            sim_units_per_second,
+            running: false,
+            anchor_wall_micros: 0,
+            anchor_sim_time: 0,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.sim_units_per_second
+    }
+
+    pub fn resume(&mut self, cm: &ComponentManager) {
+        self.resume_at(cm, now_micros())
+    }
+
+    pub fn resume_at(&mut self, cm: &ComponentManager, wall_micros: u64) {
+        if !self.running {
+            self.anchor(cm, wall_micros);
+            self.running = true;
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.running = false;
+    }
+
+    pub fn set_speed(&mut self, cm: &ComponentManager, sim_units_per_second: f64) {
+        self.set_speed_at(cm, sim_units_per_second, now_micros())
+    }
+
+    pub fn set_speed_at(
+        &mut self,
+        cm: &ComponentManager,
+        sim_units_per_second: f64,
+        wall_micros: u64,
+    ) {
+        self.anchor(cm, wall_micros);
+        self.sim_units_per_second = sim_units_per_second;
+    }
+
+    fn anchor(&mut self, cm: &ComponentManager, wall_micros: u64) {
+        self.anchor_wall_micros = wall_micros;
+        self.anchor_sim_time = cm.current_time();
+    }
+
+    /// The simulation time that corresponds to `wall_micros`.
+    pub fn target_time(&self, wall_micros: u64) -> u64 {
+        let elapsed = wall_micros.saturating_sub(self.anchor_wall_micros) as f64 / 1_000_000.0;
+        self.anchor_sim_time + (elapsed * self.sim_units_per_second) as u64
+    }
+
+    /// Runs the simulation up to the current wall time. The limits of
+    /// `budget` still apply, if they are hit the simulation falls behind and
+    /// catches up on later calls. Does nothing while paused.
+    pub fn advance(&mut self, cm: &mut ComponentManager, budget: &RunBudget) -> RunStatus {
+        self.advance_at(cm, budget, now_micros())
+    }
+
+    pub fn advance_at(
+        &mut self,
+        cm: &mut ComponentManager,
+        budget: &RunBudget,
+        wall_micros: u64,
+    ) -> RunStatus {
+        if !self.running {
+            return if cm.has_pending_events() {
+                RunStatus::Reached
+            } else {
+                RunStatus::Idle
+            };
+        }
+        let budget = RunBudget {
+            until_time: Some(self.target_time(wall_micros)),
+            ..budget.clone()
+        };
+        cm.run_budgeted(&budget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RealTimePacer;
+    use crate::{bits::Bits, run::RunBudget, ComponentManager, PinType};
+
+    #[test]
+    fn test_paced_run() {
+        let mut cm = ComponentManager::new();
+        let not = cm.create_gate("not").unwrap();
+        let input = cm.get_gate_pins(&not, &PinType::IN)[0];
+        cm.schedule_pin_update(10, input, Bits::new(1).set_num(1));
+        let budget = RunBudget::default();
+        let mut pacer = RealTimePacer::new(1000.0);
+
+        pacer.advance_at(&mut cm, &budget, 50_000);
+        assert_eq!(cm.current_time(), 0);
+
+        pacer.resume_at(&cm, 1_000_000);
+        pacer.advance_at(&mut cm, &budget, 1_005_000);
+        assert_eq!(cm.current_time(), 5);
+        assert_eq!(cm.get_pin_value(&input), Bits::new(1));
+
+        pacer.set_speed_at(&cm, 2000.0, 1_005_000);
+        pacer.advance_at(&mut cm, &budget, 1_008_000);
+        assert_eq!(cm.current_time(), 11);
+        assert_eq!(cm.get_pin_value(&input), Bits::new(1).set_num(1));
+
+        pacer.pause();
+        pacer.advance_at(&mut cm, &budget, 9_000_000);
+        assert_eq!(cm.current_time(), 11);
+        pacer.resume_at(&cm, 9_000_000);
+        pacer.advance_at(&mut cm, &budget, 9_001_000);
+        assert_eq!(cm.current_time(), 13);
+    }
+}
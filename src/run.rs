@@ -0,0 +1,146 @@
+//! Running the simulation in bounded slices, so it can share a thread with
+//! rendering or a message loop.
+
+use tracing::{info, instrument};
+
+use crate::ComponentManager;
+
+/// Limits for a single `run_budgeted` call. A run always processes at least
+/// one event if there is one, so repeated calls make progress.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunBudget {
+    pub max_events: Option<usize>,
+    pub max_micros: Option<u64>,
+    /// Simulation time to stop at. Events scheduled later stay pending and
+    /// the clock is moved forward to exactly this time.
+    pub until_time: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    /// No events are left to process.
+    Idle,
+    /// The budget ran out while events were still pending; call again to
+    /// continue where the run stopped.
+    Suspended,
+    /// `until_time` was reached, the remaining events lie in the future.
+    Reached,
+}
+
+/// Microseconds since some fixed point in the past.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now_micros() -> u64 {
+    (js_sys::Date::now() * 1000.0) as u64
+}
+
+/// Microseconds since some fixed point in the past.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_micros() -> u64 {
+    use std::{sync::OnceLock, time::Instant};
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_micros() as u64
+}
+
+impl ComponentManager {
+    /// Processes events across time steps until none are left, the budget is
+    /// used up or `until_time` is reached.
+    #[instrument(skip(self))]
+    pub fn run_budgeted(&mut self, budget: &RunBudget) -> RunStatus {
+        let start = now_micros();
+        let mut events = 0;
+        loop {
+            let Some(next_time) = self.next_event_time() else {
+                self.advance_time_to(budget.until_time);
+                return RunStatus::Idle;
+            };
+            if budget.until_time.is_some_and(|until| next_time > until) {
+                self.advance_time_to(budget.until_time);
+                return RunStatus::Reached;
+            }
+            if events > 0 {
+                let out_of_events = budget.max_events.is_some_and(|max| events >= max);
+                let out_of_time = budget
+                    .max_micros
+                    .is_some_and(|max| now_micros().saturating_sub(start) >= max);
+                if out_of_events || out_of_time {
+                    info!("Suspending after {} events", events);
+                    return RunStatus::Suspended;
+                }
+            }
+            if self.process_next_event() {
+                events += 1;
+            }
+        }
+    }
+
+    fn advance_time_to(&mut self, time: Option<u64>) {
+        if let Some(time) = time {
+            self.current_sim_time = self.current_sim_time.max(time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RunBudget, RunStatus};
+    use crate::{bits::Bits, ComponentManager, PinType};
+
+    #[test]
+    fn test_budgeted_run_resumes() {
+        let mut cm = ComponentManager::new();
+        let not = cm.create_gate("not").unwrap();
+        let lump = cm.create_lump(1);
+        cm.connect_gate_pin_to_lump(&not, &0, &PinType::OUT, &lump)
+            .unwrap();
+        let input = cm.get_gate_pins(&not, &PinType::IN)[0];
+        for t in 0..4 {
+            cm.schedule_pin_update(t * 10, input, Bits::new(1).set_num(t as i64 % 2));
+        }
+        let budget = RunBudget {
+            max_events: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(cm.run_budgeted(&budget), RunStatus::Suspended);
+        assert_eq!(cm.run_budgeted(&budget), RunStatus::Suspended);
+        assert_eq!(cm.run_budgeted(&budget), RunStatus::Idle);
+        assert_eq!(cm.current_time(), 31);
+        assert_eq!(cm.get_lump_value(&lump), Bits::new(1).set_num(0));
+    }
+
+    #[test]
+    fn test_time_budget_makes_progress() {
+        let mut cm = ComponentManager::new();
+        let not = cm.create_gate("not").unwrap();
+        let input = cm.get_gate_pins(&not, &PinType::IN)[0];
+        cm.schedule_pin_update(0, input, Bits::new(1).set_num(1));
+        let budget = RunBudget {
+            max_micros: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(cm.run_budgeted(&budget), RunStatus::Suspended);
+        assert_eq!(cm.get_pin_value(&input), Bits::new(1).set_num(1));
+        while cm.run_budgeted(&budget) == RunStatus::Suspended {}
+        assert!(!cm.has_pending_events());
+    }
+
+    #[test]
+    fn test_run_until() {
+        let mut cm = ComponentManager::new();
+        let not = cm.create_gate("not").unwrap();
+        let input = cm.get_gate_pins(&not, &PinType::IN)[0];
+        cm.schedule_pin_update(5, input, Bits::new(1).set_num(1));
+        let until = |t| RunBudget {
+            until_time: Some(t),
+            ..Default::default()
+        };
+
+        assert_eq!(cm.run_budgeted(&until(3)), RunStatus::Reached);
+        assert_eq!(cm.current_time(), 3);
+        assert_eq!(cm.get_pin_value(&input), Bits::new(1));
+        assert_eq!(cm.run_budgeted(&until(5)), RunStatus::Reached);
+        assert_eq!(cm.get_pin_value(&input), Bits::new(1).set_num(1));
+        assert_eq!(cm.run_budgeted(&until(9)), RunStatus::Idle);
+        assert_eq!(cm.current_time(), 9);
+    }
+}
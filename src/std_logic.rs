@@ -0,0 +1,238 @@
+//! The nine-valued `std_logic` type of IEEE 1164, for comparing results with
+//! VHDL simulations value for value.
+//!
+//! Simulation itself runs on `LV`. `Bits<StdLogic>` supports the bitwise
+//! operations and resolution with the tables of the standard, and converts
+//! from and to `Bits` of `LV`.
+
+use std::fmt::Display;
+
+use crate::bits::{Bits, LogicValue, LV};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StdLogic {
+    /// Uninitialized
+    U,
+    /// Forcing unknown
+    X,
+    /// Forcing 0
+    Zero,
+    /// Forcing 1
+    One,
+    /// High impedance
+    Z,
+    /// Weak unknown
+    W,
+    /// Weak 0
+    L,
+    /// Weak 1
+    H,
+    /// Don't care
+    DontCare,
+}
+
+use StdLogic::{DontCare as D, One as I, Zero as O, H, L, U, W, X, Z};
+
+/// All values in the order of the standard's tables.
+const ORDER: [StdLogic; 9] = [U, X, O, I, Z, W, L, H, D];
+
+type Table = [[StdLogic; 9]; 9];
+
+const RESOLUTION: Table = [
+    [U, U, U, U, U, U, U, U, U],
+    [U, X, X, X, X, X, X, X, X],
+    [U, X, O, X, O, O, O, O, X],
+    [U, X, X, I, I, I, I, I, X],
+    [U, X, O, I, Z, W, L, H, X],
+    [U, X, O, I, W, W, W, W, X],
+    [U, X, O, I, L, W, L, W, X],
+    [U, X, O, I, H, W, W, H, X],
+    [U, X, X, X, X, X, X, X, X],
+];
+
+const AND: Table = [
+    [U, U, O, U, U, U, O, U, U],
+    [U, X, O, X, X, X, O, X, X],
+    [O, O, O, O, O, O, O, O, O],
+    [U, X, O, I, X, X, O, I, X],
+    [U, X, O, X, X, X, O, X, X],
+    [U, X, O, X, X, X, O, X, X],
+    [O, O, O, O, O, O, O, O, O],
+    [U, X, O, I, X, X, O, I, X],
+    [U, X, O, X, X, X, O, X, X],
+];
+
+const OR: Table = [
+    [U, U, U, I, U, U, U, I, U],
+    [U, X, X, I, X, X, X, I, X],
+    [U, X, O, I, X, X, O, I, X],
+    [I, I, I, I, I, I, I, I, I],
+    [U, X, X, I, X, X, X, I, X],
+    [U, X, X, I, X, X, X, I, X],
+    [U, X, O, I, X, X, O, I, X],
+    [I, I, I, I, I, I, I, I, I],
+    [U, X, X, I, X, X, X, I, X],
+];
+
+const XOR: Table = [
+    [U, U, U, U, U, U, U, U, U],
+    [U, X, X, X, X, X, X, X, X],
+    [U, X, O, I, X, X, O, I, X],
+    [U, X, I, O, X, X, I, O, X],
+    [U, X, X, X, X, X, X, X, X],
+    [U, X, X, X, X, X, X, X, X],
+    [U, X, O, I, X, X, O, I, X],
+    [U, X, I, O, X, X, I, O, X],
+    [U, X, X, X, X, X, X, X, X],
+];
+
+const NOT: [StdLogic; 9] = [U, X, I, O, X, X, I, O, X];
+
+impl StdLogic {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn lookup(self, table: &Table, other: StdLogic) -> StdLogic {
+        table[self.index()][other.index()]
+    }
+
+    /// The strength stripped value `To_X01` of the standard.
+    pub fn to_x01(&self) -> StdLogic {
+        match self {
+            O | L => O,
+            I | H => I,
+            _ => X,
+        }
+    }
+
+    /// The closest `LV`: weak levels become strong, the unknown kinds X.
+    pub fn to_lv(&self) -> LV {
+        match self {
+            O | L => LV::L,
+            I | H => LV::H,
+            Z => LV::Z,
+            _ => LV::X,
+        }
+    }
+}
+
+impl From<LV> for StdLogic {
+    fn from(value: LV) -> Self {
+        match value {
+            LV::H => I,
+            LV::L => O,
+            LV::X => X,
+            LV::Z => Z,
+        }
+    }
+}
+
+impl Display for StdLogic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let c = ['U', 'X', '0', '1', 'Z', 'W', 'L', 'H', '-'][self.index()];
+        write!(f, "{}", c)
+    }
+}
+
+impl LogicValue for StdLogic {
+    type Store = Vec<StdLogic>;
+    fn high_z() -> Self {
+        Z
+    }
+    fn unknown() -> Self {
+        X
+    }
+    fn from_bool(value: bool) -> Self {
+        if value {
+            I
+        } else {
+            O
+        }
+    }
+    fn to_bool(&self) -> Option<bool> {
+        match self.to_x01() {
+            I => Some(true),
+            O => Some(false),
+            _ => None,
+        }
+    }
+    fn and(&self, other: &Self) -> Self {
+        self.lookup(&AND, *other)
+    }
+    fn or(&self, other: &Self) -> Self {
+        self.lookup(&OR, *other)
+    }
+    fn not(&self) -> Self {
+        NOT[self.index()]
+    }
+    fn xor(&self, other: &Self) -> Self {
+        self.lookup(&XOR, *other)
+    }
+    fn resolve(&self, other: &Self) -> Self {
+        self.lookup(&RESOLUTION, *other)
+    }
+    /// Parses the characters VHDL uses, case insensitive.
+    fn from_char(c: char) -> Option<Self> {
+        let c = c.to_ascii_uppercase();
+        ORDER.into_iter().find(|v| v.to_string() == c.to_string())
+    }
+}
+
+impl Bits {
+    pub fn to_std_logic(&self) -> Bits<StdLogic> {
+        self.map(|lv| StdLogic::from(lv.clone()))
+    }
+}
+
+impl Bits<StdLogic> {
+    /// See `StdLogic::to_lv`.
+    pub fn to_lv(&self) -> Bits {
+        self.map(StdLogic::to_lv)
+    }
+
+    /// Resolves any number of drivers like the `resolved` function of the
+    /// standard, all undriven gives all Z.
+    pub fn resolve_all<'a>(width: usize, drivers: impl Iterator<Item = &'a Self>) -> Self {
+        drivers.fold(Bits::new(width), |acc, d| acc.resolve(d))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Bits<StdLogic> {
+        s.chars().map(|c| StdLogic::from_char(c).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_tables() {
+        // Rows of the standard's tables against all values in order
+        let all = parse("UX01ZWLH-");
+        let row = |op: fn(&Bits<StdLogic>, &Bits<StdLogic>) -> Bits<StdLogic>, v: &str| {
+            op(&parse(&v.repeat(9)), &all).to_string()
+        };
+        assert_eq!(row(Bits::resolve, "L"), "UX01LWLWX");
+        assert_eq!(row(Bits::resolve, "Z"), "UX01ZWLHX");
+        assert_eq!(row(Bits::and, "H"), "UX01XX01X");
+        assert_eq!(row(Bits::or, "L"), "UX01XX01X");
+        assert_eq!(row(Bits::or, "U"), "UUU1UUU1U");
+        assert_eq!(all.not().to_string(), "UX10XX10X");
+        assert_eq!(H.xor(&L), I);
+        assert_eq!(StdLogic::from_char('w'), Some(W));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let lv: Bits = "10XZ".chars().map(|c| LV::from_char(c).unwrap()).collect();
+        assert_eq!(lv.to_std_logic().to_string(), "10XZ");
+        assert_eq!(parse("LH-UW").to_lv().to_string(), "01XXX");
+        assert_eq!(H.to_bool(), Some(true));
+        assert_eq!(W.to_bool(), None);
+
+        let drivers = [parse("0ZH"), parse("ZLL")];
+        let resolved = Bits::resolve_all(3, drivers.iter());
+        assert_eq!(resolved.to_string(), "0LW");
+    }
+}
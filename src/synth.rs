@@ -0,0 +1,671 @@
+//! Gate level circuits built from `Equations` or a `TruthTable`, so the
+//! structure can be looked at and simulated gate by gate instead of as one
+//! opaque component.
+//!
+//! A `Network` is a graph of one bit nodes made of `and`, `or` and `not`
+//! gates or of `nand` gates only. Equal nodes are shared, constants folded
+//! and double negations dropped while it is built. Truth tables and
+//! equations can be minimized to two levels first, see `minimize`.
+//! `Network::build` then creates the gates and lumps in a
+//! `ComponentManager`.
+
+use std::collections::HashMap;
+
+use crate::{
+    bits::{Bits, LV},
+    expr::{Equations, Expr},
+    minimize::{minimize, minimize_table, Cube, Mode},
+    params::{Param, Params},
+    truth_table::{Entry, TruthTable, MAX_INPUTS},
+    ComponentManager, PinType,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    AndOrNot,
+    Nand,
+}
+
+/// Operands are indices of earlier nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Node {
+    /// Bit `bit` of input `input`.
+    Input {
+        input: usize,
+        bit: usize,
+    },
+    Const(bool),
+    Not(usize),
+    And(usize, usize),
+    Or(usize, usize),
+    Nand(usize, usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Network {
+    style: Style,
+    inputs: Vec<(String, usize)>,
+    outputs: Vec<(String, usize)>,
+    nodes: Vec<Node>,
+    shared: HashMap<Node, usize>,
+    /// The node driving each output bit.
+    output_nodes: Vec<Vec<usize>>,
+}
+
+/// What `Network::build` created: a lump per input and output, in
+/// declaration order, and all gates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Circuit {
+    pub inputs: Vec<usize>,
+    pub outputs: Vec<usize>,
+    pub gates: Vec<usize>,
+}
+
+impl Network {
+    fn new(style: Style, inputs: Vec<(String, usize)>, outputs: Vec<(String, usize)>) -> Network {
+        Network {
+            style,
+            inputs,
+            outputs,
+            nodes: Vec::new(),
+            shared: HashMap::new(),
+            output_nodes: Vec::new(),
+        }
+    }
+
+    /// One gate per operator, with xor as `a & ~b | ~a & b`.
+    pub fn from_equations(equations: &Equations, style: Style) -> Result<Network, String> {
+        let mut network = Network::new(
+            style,
+            equations.inputs().to_vec(),
+            equations.outputs().to_vec(),
+        );
+        let mut outputs: Vec<Vec<Option<usize>>> = equations
+            .outputs()
+            .iter()
+            .map(|(_, width)| vec![None; *width])
+            .collect();
+        for a in equations.assignments() {
+            for (i, node) in network.expr(&a.expr)?.into_iter().enumerate() {
+                outputs[a.output][a.start + i] = Some(node);
+            }
+        }
+        network.output_nodes = outputs
+            .into_iter()
+            .map(|bits| {
+                bits.into_iter()
+                    .map(|n| n.expect("Unassigned bit"))
+                    .collect()
+            })
+            .collect();
+        Ok(network)
+    }
+
+    /// A sum of products with one product per input combination giving 1.
+    /// Outputs that are X, don't care or not listed come out 0.
+    pub fn from_truth_table(table: &TruthTable, style: Style) -> Result<Network, String> {
+        let expanded = table.expand()?;
+        let covers: Vec<Vec<Cube>> = (0..table.outputs().len())
+            .map(|out| {
+                (0..expanded.len())
+                    .filter(|index| expanded[*index][out] == Entry::High)
+                    .map(|index| Cube::minterm(table.inputs().len(), index as u64))
+                    .collect()
+            })
+            .collect();
+        Ok(Network::from_table_covers(table, &covers, style))
+    }
+
+    /// A sum of products minimized by `minimize_table`.
+    pub fn from_truth_table_minimized(
+        table: &TruthTable,
+        style: Style,
+        mode: Mode,
+    ) -> Result<Network, String> {
+        let covers = minimize_table(table, mode)?;
+        Ok(Network::from_table_covers(table, &covers, style))
+    }
+
+    fn from_table_covers(table: &TruthTable, covers: &[Vec<Cube>], style: Style) -> Network {
+        let mut network = Network::new(
+            style,
+            table.inputs().iter().map(|n| (n.clone(), 1)).collect(),
+            table.outputs().iter().map(|n| (n.clone(), 1)).collect(),
+        );
+        let bits: Vec<Node> = (0..table.inputs().len())
+            .map(|input| Node::Input { input, bit: 0 })
+            .collect();
+        for cover in covers {
+            let node = network.sum_of_products(&bits, cover);
+            network.output_nodes.push(vec![node]);
+        }
+        network
+    }
+
+    /// A minimized sum of products for every output bit, over all input
+    /// bits together, so they may be at most `truth_table::MAX_INPUTS`.
+    /// Output bits that are X may come out either way.
+    pub fn from_equations_minimized(
+        equations: &Equations,
+        style: Style,
+        mode: Mode,
+    ) -> Result<Network, String> {
+        let bits: Vec<Node> = equations
+            .inputs()
+            .iter()
+            .enumerate()
+            .flat_map(|(input, (_, width))| (0..*width).map(move |bit| Node::Input { input, bit }))
+            .collect();
+        if bits.len() > MAX_INPUTS {
+            return Err(format!(
+                "Minimizing takes equations with at most {} input bits",
+                MAX_INPUTS
+            ));
+        }
+        let output_bits: usize = equations.outputs().iter().map(|(_, width)| width).sum();
+        let (mut on, mut dont_care) =
+            (vec![Vec::new(); output_bits], vec![Vec::new(); output_bits]);
+        for index in 0..1u64 << bits.len() {
+            let mut shift = 0;
+            let inputs: Vec<Bits> = equations
+                .inputs()
+                .iter()
+                .map(|(_, width)| {
+                    let value = Bits::from_u64(*width, index >> shift & ((1 << width) - 1));
+                    shift += width;
+                    value.unwrap()
+                })
+                .collect();
+            let outputs = equations.evaluate(&inputs);
+            for (k, lv) in outputs.iter().flat_map(|bits| bits.iter()).enumerate() {
+                match lv {
+                    LV::H => on[k].push(Cube::minterm(bits.len(), index)),
+                    LV::L => {}
+                    _ => dont_care[k].push(Cube::minterm(bits.len(), index)),
+                }
+            }
+        }
+
+        let mut network = Network::new(
+            style,
+            equations.inputs().to_vec(),
+            equations.outputs().to_vec(),
+        );
+        let mut nodes = Vec::new();
+        for (on, dont_care) in on.iter().zip(&dont_care) {
+            let cover = minimize(bits.len(), on, dont_care, mode)?;
+            nodes.push(network.sum_of_products(&bits, &cover));
+        }
+        let mut nodes = nodes.into_iter();
+        network.output_nodes = equations
+            .outputs()
+            .iter()
+            .map(|(_, width)| nodes.by_ref().take(*width).collect())
+            .collect();
+        Ok(network)
+    }
+
+    fn node(&mut self, node: Node) -> usize {
+        // Operands in a fixed order so `a & b` and `b & a` are shared
+        let node = match node {
+            Node::And(a, b) => Node::And(a.min(b), a.max(b)),
+            Node::Or(a, b) => Node::Or(a.min(b), a.max(b)),
+            Node::Nand(a, b) => Node::Nand(a.min(b), a.max(b)),
+            node => node,
+        };
+        if let Some(n) = self.shared.get(&node) {
+            return *n;
+        }
+        self.nodes.push(node);
+        self.shared.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn constant(&self, n: usize) -> Option<bool> {
+        match self.nodes[n] {
+            Node::Const(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn not(&mut self, a: usize) -> usize {
+        match (self.nodes[a], self.style) {
+            (Node::Const(value), _) => self.node(Node::Const(!value)),
+            (Node::Not(x), _) => x,
+            (Node::Nand(x, y), _) if x == y => x,
+            (_, Style::AndOrNot) => self.node(Node::Not(a)),
+            (_, Style::Nand) => self.node(Node::Nand(a, a)),
+        }
+    }
+
+    fn and(&mut self, a: usize, b: usize) -> usize {
+        match (self.constant(a), self.constant(b)) {
+            (Some(false), _) | (_, Some(false)) => self.node(Node::Const(false)),
+            (Some(true), _) => b,
+            (_, Some(true)) => a,
+            _ if a == b => a,
+            _ => match self.style {
+                Style::AndOrNot => self.node(Node::And(a, b)),
+                Style::Nand => {
+                    let nand = self.node(Node::Nand(a, b));
+                    self.not(nand)
+                }
+            },
+        }
+    }
+
+    fn or(&mut self, a: usize, b: usize) -> usize {
+        match (self.constant(a), self.constant(b)) {
+            (Some(true), _) | (_, Some(true)) => self.node(Node::Const(true)),
+            (Some(false), _) => b,
+            (_, Some(false)) => a,
+            _ if a == b => a,
+            _ => match self.style {
+                Style::AndOrNot => self.node(Node::Or(a, b)),
+                Style::Nand => {
+                    let (not_a, not_b) = (self.not(a), self.not(b));
+                    self.node(Node::Nand(not_a, not_b))
+                }
+            },
+        }
+    }
+
+    fn xor(&mut self, a: usize, b: usize) -> usize {
+        let (not_a, not_b) = (self.not(a), self.not(b));
+        let (left, right) = (self.and(a, not_b), self.and(not_a, b));
+        self.or(left, right)
+    }
+
+    /// Ors together the products of `cover`, whose input `i` is `bits[i]`.
+    fn sum_of_products(&mut self, bits: &[Node], cover: &[Cube]) -> usize {
+        let mut sum = self.node(Node::Const(false));
+        for cube in cover {
+            let mut product = self.node(Node::Const(true));
+            for (i, level) in cube.literals() {
+                let mut literal = self.node(bits[i]);
+                if !level {
+                    literal = self.not(literal);
+                }
+                product = self.and(product, literal);
+            }
+            sum = self.or(sum, product);
+        }
+        sum
+    }
+
+    /// The nodes of every bit of `expr`, least significant first.
+    fn expr(&mut self, expr: &Expr) -> Result<Vec<usize>, String> {
+        let bitwise = |network: &mut Network,
+                       a: &Expr,
+                       b: &Expr,
+                       op: fn(&mut Network, usize, usize) -> usize| {
+            let (a, b) = (network.expr(a)?, network.expr(b)?);
+            Ok(a.into_iter()
+                .zip(b)
+                .map(|(a, b)| op(network, a, b))
+                .collect())
+        };
+        let reduce = |network: &mut Network,
+                      e: &Expr,
+                      start: bool,
+                      op: fn(&mut Network, usize, usize) -> usize| {
+            let bits = network.expr(e)?;
+            let start = network.node(Node::Const(start));
+            Ok(vec![bits
+                .into_iter()
+                .fold(start, |acc, n| op(network, acc, n))])
+        };
+        match expr {
+            Expr::Input { input, start, end } => Ok((*start..*end)
+                .map(|bit| self.node(Node::Input { input: *input, bit }))
+                .collect()),
+            Expr::Const(bits) => bits
+                .iter()
+                .map(|lv| match lv {
+                    LV::H | LV::L => Ok(self.node(Node::Const(lv == LV::H))),
+                    _ => Err("Constants need to be 0 or 1 to be built from gates".to_string()),
+                })
+                .collect(),
+            Expr::Not(e) => Ok(self.expr(e)?.into_iter().map(|n| self.not(n)).collect()),
+            Expr::And(a, b) => bitwise(self, a, b, Network::and),
+            Expr::Or(a, b) => bitwise(self, a, b, Network::or),
+            Expr::Xor(a, b) => bitwise(self, a, b, Network::xor),
+            Expr::Concat(parts) => {
+                let mut bits = Vec::new();
+                for part in parts.iter().rev() {
+                    bits.extend(self.expr(part)?);
+                }
+                Ok(bits)
+            }
+            Expr::AndReduce(e) => reduce(self, e, true, Network::and),
+            Expr::OrReduce(e) => reduce(self, e, false, Network::or),
+            Expr::XorReduce(e) => reduce(self, e, false, Network::xor),
+        }
+    }
+
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    pub fn inputs(&self) -> &[(String, usize)] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[(String, usize)] {
+        &self.outputs
+    }
+
+    /// All nodes, each after its operands. Some may not drive any output.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// The node driving each bit of each output.
+    pub fn output_nodes(&self) -> &[Vec<usize>] {
+        &self.output_nodes
+    }
+
+    /// Which nodes some output depends on.
+    fn used(&self) -> Vec<bool> {
+        let mut used = vec![false; self.nodes.len()];
+        for n in self.output_nodes.iter().flatten() {
+            used[*n] = true;
+        }
+        for n in (0..self.nodes.len()).rev() {
+            if !used[n] {
+                continue;
+            }
+            match self.nodes[n] {
+                Node::Not(a) => used[a] = true,
+                Node::And(a, b) | Node::Or(a, b) | Node::Nand(a, b) => {
+                    used[a] = true;
+                    used[b] = true;
+                }
+                Node::Input { .. } | Node::Const(_) => {}
+            }
+        }
+        used
+    }
+
+    /// How many logic gates `build` creates, without splitters, mergers
+    /// and constants.
+    pub fn gate_count(&self) -> usize {
+        self.used()
+            .iter()
+            .zip(&self.nodes)
+            .filter(|(used, node)| **used && !matches!(node, Node::Input { .. } | Node::Const(_)))
+            .count()
+    }
+
+    /// The output values for the given input values, as the gates would
+    /// compute them.
+    pub fn evaluate(&self, inputs: &[Bits]) -> Vec<Bits> {
+        let mut values: Vec<LV> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let value = match *node {
+                Node::Input { input, bit } => inputs[input].get(bit),
+                Node::Const(value) => LV::from(value),
+                Node::Not(a) => values[a].not(),
+                Node::And(a, b) => values[a].and(&values[b]),
+                Node::Or(a, b) => values[a].or(&values[b]),
+                Node::Nand(a, b) => values[a].nand(&values[b]),
+            };
+            values.push(value);
+        }
+        self.output_nodes
+            .iter()
+            .map(|bits| bits.iter().map(|n| values[*n].clone()).collect())
+            .collect()
+    }
+
+    /// Creates the gates and lumps as one undo step. Bus inputs are split
+    /// into bits by a `splitter` and bus outputs joined by a `merger`. A 1
+    /// bit output driven straight by an input or another output shares its
+    /// lump. A failed build leaves the netlist unchanged.
+    pub fn build(&self, cm: &mut ComponentManager) -> Result<Circuit, String> {
+        cm.begin_transaction();
+        match self.build_gates(cm) {
+            Ok(circuit) => {
+                cm.commit_transaction()?;
+                Ok(circuit)
+            }
+            Err(e) => {
+                cm.abort_transaction()?;
+                Err(e)
+            }
+        }
+    }
+
+    fn build_gates(&self, cm: &mut ComponentManager) -> Result<Circuit, String> {
+        let mut circuit = Circuit {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            gates: Vec::new(),
+        };
+        let bus = |kind: &str, width: usize, cm: &mut ComponentManager| {
+            let params = Params::new().with("width", Param::Int(width as i64));
+            cm.create_gate_with_params(kind, params)
+        };
+
+        let mut input_bits = Vec::new();
+        for (_, width) in &self.inputs {
+            let lump = cm.create_lump(*width);
+            circuit.inputs.push(lump);
+            if *width == 1 {
+                input_bits.push(vec![lump]);
+                continue;
+            }
+            let splitter = bus("splitter", *width, cm)?;
+            circuit.gates.push(splitter);
+            cm.connect_gate_pin_to_lump(&splitter, &0, &PinType::IN, &lump)?;
+            let mut bits = Vec::new();
+            for bit in 0..*width {
+                let lump = cm.create_lump(1);
+                cm.connect_gate_pin_to_lump(&splitter, &bit, &PinType::OUT, &lump)?;
+                bits.push(lump);
+            }
+            input_bits.push(bits);
+        }
+
+        let used = self.used();
+        let mut lumps = vec![0; self.nodes.len()];
+        for (n, node) in self.nodes.iter().enumerate() {
+            if !used[n] {
+                continue;
+            }
+            let (kind, operands) = match *node {
+                Node::Input { input, bit } => {
+                    lumps[n] = input_bits[input][bit];
+                    continue;
+                }
+                Node::Const(true) => ("vcc", vec![]),
+                Node::Const(false) => ("gnd", vec![]),
+                Node::Not(a) => ("not", vec![a]),
+                Node::And(a, b) => ("and", vec![a, b]),
+                Node::Or(a, b) => ("or", vec![a, b]),
+                Node::Nand(a, b) => ("nand", vec![a, b]),
+            };
+            let gate = cm.create_gate(kind)?;
+            circuit.gates.push(gate);
+            for (pin, operand) in operands.iter().enumerate() {
+                cm.connect_gate_pin_to_lump(&gate, &pin, &PinType::IN, &lumps[*operand])?;
+            }
+            lumps[n] = cm.create_lump(1);
+            cm.connect_gate_pin_to_lump(&gate, &0, &PinType::OUT, &lumps[n])?;
+        }
+
+        for bits in &self.output_nodes {
+            if let [n] = bits[..] {
+                circuit.outputs.push(lumps[n]);
+                continue;
+            }
+            let merger = bus("merger", bits.len(), cm)?;
+            circuit.gates.push(merger);
+            for (bit, n) in bits.iter().enumerate() {
+                cm.connect_gate_pin_to_lump(&merger, &bit, &PinType::IN, &lumps[*n])?;
+            }
+            let lump = cm.create_lump(bits.len());
+            cm.connect_gate_pin_to_lump(&merger, &0, &PinType::OUT, &lump)?;
+            circuit.outputs.push(lump);
+        }
+        Ok(circuit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::RunBudget;
+
+    /// Every combination of input values, counting up.
+    fn combinations(widths: &[usize]) -> Vec<Vec<Bits>> {
+        let total: usize = widths.iter().sum();
+        (0..1u64 << total)
+            .map(|n| {
+                let mut shift = 0;
+                widths
+                    .iter()
+                    .map(|w| {
+                        let value = Bits::from_u64(*w, n >> shift & ((1 << w) - 1)).unwrap();
+                        shift += w;
+                        value
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Checks `network` in the simulator against `expected` for all inputs.
+    fn simulate(network: &Network, expected: impl Fn(&[Bits]) -> Vec<Bits>) {
+        let mut cm = ComponentManager::new();
+        let circuit = network.build(&mut cm).unwrap();
+        let mut sources = Vec::new();
+        for ((_, width), lump) in network.inputs().iter().zip(&circuit.inputs) {
+            let params = Params::new().with("width", Param::Int(*width as i64));
+            let source = cm.create_gate_with_params("input", params).unwrap();
+            cm.connect_gate_pin_to_lump(&source, &0, &PinType::OUT, lump)
+                .unwrap();
+            sources.push(source);
+        }
+        let widths: Vec<usize> = network.inputs().iter().map(|(_, w)| *w).collect();
+        for inputs in combinations(&widths) {
+            for (source, value) in sources.iter().zip(&inputs) {
+                cm.set_input(source, value.clone()).unwrap();
+            }
+            cm.run_budgeted(&RunBudget::default());
+            let outputs: Vec<Bits> = circuit
+                .outputs
+                .iter()
+                .map(|l| cm.get_lump_value(l))
+                .collect();
+            assert_eq!(outputs, expected(&inputs), "{:?}", inputs);
+        }
+    }
+
+    #[test]
+    fn test_from_equations() {
+        let equations = Equations::parse(
+            "input a, b, cin; input [1:0] d; output sum, carry; output [2:0] y;
+             sum = a ^ b ^ cin; carry = a&b | cin&(a^b);
+             y = {~&d, d & {a, 1'b1}}",
+        )
+        .unwrap();
+        for style in [Style::AndOrNot, Style::Nand] {
+            let network = Network::from_equations(&equations, style).unwrap();
+            for inputs in combinations(&[1, 1, 1, 2]) {
+                assert_eq!(network.evaluate(&inputs), equations.evaluate(&inputs));
+            }
+            if style == Style::Nand {
+                assert!(network
+                    .nodes()
+                    .iter()
+                    .all(|n| !matches!(n, Node::Not(_) | Node::And(..) | Node::Or(..))));
+            }
+            simulate(&network, |inputs| equations.evaluate(inputs));
+        }
+
+        let xor = Equations::parse("input a, b; output y; y = a ^ b").unwrap();
+        let network = Network::from_equations(&xor, Style::AndOrNot).unwrap();
+        assert_eq!(network.gate_count(), 5);
+        let network = Network::from_equations(&xor, Style::Nand).unwrap();
+        assert_eq!(network.gate_count(), 5);
+        let x = Equations::parse("input a; output y; y = a & 1'bx").unwrap();
+        assert!(Network::from_equations(&x, Style::Nand).is_err());
+    }
+
+    #[test]
+    fn test_from_truth_table() {
+        let mut table = TruthTable::new(&["s", "a", "b"], &["y"]).unwrap();
+        table.add_row("00-", "0").unwrap();
+        table.add_row("01-", "1").unwrap();
+        table.add_row("1-0", "0").unwrap();
+        table.add_row("1-1", "1").unwrap();
+        for style in [Style::AndOrNot, Style::Nand] {
+            let network = Network::from_truth_table(&table, style).unwrap();
+            simulate(&network, |inputs| {
+                let levels: Vec<LV> = inputs.iter().map(|b| b.get(0)).collect();
+                vec![table.evaluate(&levels).unwrap()]
+            });
+        }
+        // Three inverters, seven ands as `~s & a` is shared and three ors
+        let network = Network::from_truth_table(&table, Style::AndOrNot).unwrap();
+        assert_eq!(network.gate_count(), 13);
+        // ~s & a | s & b
+        let network =
+            Network::from_truth_table_minimized(&table, Style::AndOrNot, Mode::Exact).unwrap();
+        assert_eq!(network.gate_count(), 4);
+        simulate(&network, |inputs| {
+            let levels: Vec<LV> = inputs.iter().map(|b| b.get(0)).collect();
+            vec![table.evaluate(&levels).unwrap()]
+        });
+    }
+
+    #[test]
+    fn test_from_equations_minimized() {
+        let equations = Equations::parse(
+            "input a, b, cin; input [1:0] d; output carry; output [1:0] y;
+             carry = a&b | cin&(a^b); y = ~(d | {a, a})",
+        )
+        .unwrap();
+        for mode in [Mode::Exact, Mode::Heuristic] {
+            let network =
+                Network::from_equations_minimized(&equations, Style::AndOrNot, mode).unwrap();
+            // a&b | a&cin | b&cin and ~d[i] & ~a with three inverters
+            assert_eq!(network.gate_count(), 5 + 5, "{:?}", mode);
+            simulate(&network, |inputs| equations.evaluate(inputs));
+        }
+        let wide = Equations::parse("input [16:0] a; output y; y = &a").unwrap();
+        assert!(Network::from_equations_minimized(&wide, Style::Nand, Mode::Auto).is_err());
+    }
+
+    #[test]
+    fn test_failed_build_is_reverted() {
+        let equations =
+            Equations::parse("input [1:0] a; output y; y = a[0] & a[1] | a[0]").unwrap();
+        let network = Network::from_equations(&equations, Style::AndOrNot).unwrap();
+        let mut cm = ComponentManager::new();
+        cm.component_library.constructors.remove("or");
+        assert!(network.build(&mut cm).is_err());
+        assert!(cm.gates.read().unwrap().is_empty());
+        assert!(cm.lumps.read().unwrap().is_empty());
+        assert!(cm.pins.read().unwrap().is_empty());
+        assert!(!cm.can_undo());
+
+        // Inside an outer transaction only the build is reverted
+        let lump = cm.create_lump(1);
+        cm.begin_transaction();
+        let gate = cm.create_gate("not").unwrap();
+        assert!(network.build(&mut cm).is_err());
+        cm.commit_transaction().unwrap();
+        assert_eq!(
+            cm.gates.read().unwrap().keys().collect::<Vec<_>>(),
+            vec![&gate]
+        );
+        assert_eq!(
+            cm.lumps.read().unwrap().keys().collect::<Vec<_>>(),
+            vec![&lump]
+        );
+        assert!(cm.undo().unwrap());
+        assert!(cm.gates.read().unwrap().is_empty());
+    }
+}
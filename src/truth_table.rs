@@ -0,0 +1,322 @@
+//! Combinational components described by a truth table, built through the
+//! API or read from CSV, so new components need no Rust code.
+//!
+//! Every input and output is one bit. Rows list input patterns, where `-`
+//! matches both levels, and the outputs for them, where `X` drives an
+//! unknown level and `-` means the output doesn't matter. Input
+//! combinations no row covers give X outputs.
+
+use crate::bits::{Bits, LV};
+
+/// Most inputs a table may have, the gate keeps all combinations.
+pub const MAX_INPUTS: usize = 16;
+
+/// One cell of a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry {
+    Low,
+    High,
+    /// Only for outputs: the output is X.
+    Unknown,
+    /// Any level: matches both for inputs, may be anything for outputs.
+    DontCare,
+}
+
+impl Entry {
+    fn from_char(c: char) -> Option<Entry> {
+        match c {
+            '0' => Some(Entry::Low),
+            '1' => Some(Entry::High),
+            'x' | 'X' => Some(Entry::Unknown),
+            '-' => Some(Entry::DontCare),
+            _ => None,
+        }
+    }
+
+    /// The level the simulation drives, X unless it is known.
+    pub fn to_lv(self) -> LV {
+        match self {
+            Entry::Low => LV::L,
+            Entry::High => LV::H,
+            _ => LV::X,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub inputs: Vec<Entry>,
+    pub outputs: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthTable {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    rows: Vec<Row>,
+}
+
+/// Identifiers that aren't `input` or `output`, so tables and equations
+/// accept the same names and one can be written out as the other.
+pub(crate) fn check_signal_name(name: &str) -> Result<(), String> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid || name == "input" || name == "output" {
+        return Err(format!("Invalid signal name '{}'", name));
+    }
+    Ok(())
+}
+
+fn check_names(names: &[&str], seen: &mut Vec<String>) -> Result<(), String> {
+    for name in names {
+        check_signal_name(name)?;
+        if seen.iter().any(|s| s == name) {
+            return Err(format!("Signal {} is declared twice", name));
+        }
+        seen.push(name.to_string());
+    }
+    Ok(())
+}
+
+fn parse_entries(pattern: &str, count: usize, what: &str) -> Result<Vec<Entry>, String> {
+    let entries = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| Entry::from_char(c).ok_or(format!("'{}' is not a truth table entry", c)))
+        .collect::<Result<Vec<Entry>, String>>()?;
+    if entries.len() != count {
+        return Err(format!(
+            "Expected {} {} but got {} in '{}'",
+            count,
+            what,
+            entries.len(),
+            pattern
+        ));
+    }
+    Ok(entries)
+}
+
+impl TruthTable {
+    pub fn new(inputs: &[&str], outputs: &[&str]) -> Result<TruthTable, String> {
+        if inputs.len() > MAX_INPUTS {
+            return Err(format!("A truth table takes at most {} inputs", MAX_INPUTS));
+        }
+        if outputs.is_empty() {
+            return Err("A truth table needs an output".to_string());
+        }
+        let mut names = Vec::new();
+        check_names(inputs, &mut names)?;
+        check_names(outputs, &mut names)?;
+        Ok(TruthTable {
+            inputs: inputs.iter().map(|s| s.to_string()).collect(),
+            outputs: outputs.iter().map(|s| s.to_string()).collect(),
+            rows: Vec::new(),
+        })
+    }
+
+    /// Adds a row given as one character per signal in declaration order,
+    /// e.g. `add_row("01-", "1X")`.
+    pub fn add_row(&mut self, inputs: &str, outputs: &str) -> Result<(), String> {
+        let inputs = parse_entries(inputs, self.inputs.len(), "inputs")?;
+        if inputs.contains(&Entry::Unknown) {
+            return Err("Inputs take 0, 1 or - for both".to_string());
+        }
+        let outputs = parse_entries(outputs, self.outputs.len(), "outputs")?;
+        self.rows.push(Row { inputs, outputs });
+        Ok(())
+    }
+
+    /// Reads a header naming the inputs, a `|` column and the outputs,
+    /// followed by one line per row in the same columns, e.g.
+    ///
+    /// ```text
+    /// a,b,|,y
+    /// 1,1,|,1
+    /// 0,-,|,0
+    /// ```
+    ///
+    /// Blank lines and lines starting with `#` are skipped.
+    pub fn from_csv(text: &str) -> Result<TruthTable, String> {
+        let mut lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        let header: Vec<&str> = lines
+            .next()
+            .ok_or("The truth table is empty")?
+            .split(',')
+            .map(str::trim)
+            .collect();
+        let split = header
+            .iter()
+            .position(|c| *c == "|")
+            .ok_or("The header needs a | column between inputs and outputs")?;
+        let mut table = TruthTable::new(&header[..split], &header[split + 1..])?;
+        for line in lines {
+            let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+            if cells.len() != header.len() || !matches!(cells[split], "|" | "") {
+                return Err(format!("Row '{}' doesn't match the header", line));
+            }
+            table.add_row(&cells[..split].concat(), &cells[split + 1..].concat())?;
+        }
+        Ok(table)
+    }
+
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[String] {
+        &self.outputs
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    /// The outputs of every input combination, bit `i` of the index being
+    /// input `i`. Rows may overlap as long as they agree where both care.
+    pub fn expand(&self) -> Result<Vec<Vec<Entry>>, String> {
+        let n = self.inputs.len();
+        // Each entry with the row that set it
+        let mut expanded = vec![vec![(Entry::DontCare, 0); self.outputs.len()]; 1 << n];
+        let mut covered = vec![false; 1 << n];
+        for (r, row) in self.rows.iter().enumerate() {
+            let free: Vec<usize> = (0..n)
+                .filter(|i| row.inputs[*i] == Entry::DontCare)
+                .collect();
+            let base: usize = (0..n)
+                .filter(|i| row.inputs[*i] == Entry::High)
+                .map(|i| 1 << i)
+                .sum();
+            for combination in 0..1usize << free.len() {
+                let index = free
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| combination >> j & 1 == 1)
+                    .fold(base, |index, (_, i)| index | 1 << i);
+                for (current, entry) in expanded[index].iter_mut().zip(&row.outputs) {
+                    match (current.0, *entry) {
+                        (_, Entry::DontCare) => {}
+                        (Entry::DontCare, _) => *current = (*entry, r),
+                        (a, b) if a == b => {}
+                        _ => {
+                            return Err(format!("Row {} contradicts row {}", r + 1, current.1 + 1))
+                        }
+                    }
+                }
+                covered[index] = true;
+            }
+        }
+        let mut expanded: Vec<Vec<Entry>> = expanded
+            .into_iter()
+            .map(|outputs| outputs.into_iter().map(|(entry, _)| entry).collect())
+            .collect();
+        for (outputs, covered) in expanded.iter_mut().zip(&covered) {
+            if !covered {
+                outputs.fill(Entry::Unknown);
+            }
+        }
+        Ok(expanded)
+    }
+
+    /// The outputs for the given input levels, see `evaluate_expanded`.
+    pub fn evaluate(&self, inputs: &[LV]) -> Result<Bits, String> {
+        let levels: Vec<Bits> = self
+            .expand()?
+            .iter()
+            .map(|outputs| outputs.iter().map(|e| e.to_lv()).collect())
+            .collect();
+        Ok(evaluate_expanded(&levels, inputs))
+    }
+}
+
+/// Looks up the outputs for `inputs` in an expanded table. X and Z inputs
+/// stand for both levels, outputs stay known where all those combinations
+/// agree.
+pub(crate) fn evaluate_expanded(table: &[Bits], inputs: &[LV]) -> Bits {
+    let unknown: Vec<usize> = (0..inputs.len())
+        .filter(|i| !matches!(inputs[*i], LV::H | LV::L))
+        .collect();
+    let base: usize = (0..inputs.len())
+        .filter(|i| inputs[*i] == LV::H)
+        .map(|i| 1 << i)
+        .sum();
+    let mut result = table[base].clone();
+    for combination in 1..1usize << unknown.len() {
+        let index = unknown
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| combination >> j & 1 == 1)
+            .fold(base, |index, (_, i)| index | 1 << i);
+        let other = &table[index];
+        result = result
+            .iter()
+            .zip(other.iter())
+            .map(|(a, b)| if a == b { a } else { LV::X })
+            .collect();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL_ADDER: &str = "
+        # full adder
+        a, b, cin, |, sum, cout
+        0, 0, 0,   |, 0,   0
+        1, 0, 0,   |, 1,   0
+        0, 1, 0,   |, 1,   0
+        1, 1, 0,   |, 0,   1
+        0, 0, 1,   |, 1,   0
+        1, 0, 1,   |, 0,   1
+        0, 1, 1,   |, 0,   1
+        1, 1, 1,   |, 1,   1
+    ";
+
+    fn levels(s: &str) -> Vec<LV> {
+        s.chars().map(|c| LV::from_char(c).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_csv() {
+        let table = TruthTable::from_csv(FULL_ADDER).unwrap();
+        assert_eq!(table.inputs(), ["a", "b", "cin"]);
+        assert_eq!(table.rows().len(), 8);
+        assert_eq!(table.evaluate(&levels("110")).unwrap().to_string(), "01");
+        assert_eq!(table.evaluate(&levels("111")).unwrap().to_string(), "11");
+        // The carry is 1 whatever cin is, the sum isn't known
+        assert_eq!(table.evaluate(&levels("11X")).unwrap().to_string(), "X1");
+        assert_eq!(table.evaluate(&levels("0Z0")).unwrap().to_string(), "X0");
+
+        assert!(TruthTable::from_csv("a,b,y\n0,0,0").is_err());
+        assert!(TruthTable::from_csv("a,|,y\n0,|,0,1").is_err());
+        assert!(TruthTable::from_csv("a,a,|,y").is_err());
+        assert!(TruthTable::from_csv("input,|,y").is_err());
+    }
+
+    #[test]
+    fn test_dont_cares_and_gaps() {
+        let mut table = TruthTable::new(&["s", "a", "b"], &["y"]).unwrap();
+        // A multiplexer without the rows for s = 1 and b = 0
+        table.add_row("00-", "0").unwrap();
+        table.add_row("01-", "1").unwrap();
+        table.add_row("1-1", "1").unwrap();
+        // Overlaps with the two rows before and agrees
+        table.add_row("-11", "1").unwrap();
+        assert_eq!(table.evaluate(&levels("011")).unwrap().to_string(), "1");
+        assert_eq!(table.evaluate(&levels("100")).unwrap().to_string(), "X");
+        assert_eq!(table.evaluate(&levels("X11")).unwrap().to_string(), "1");
+        assert_eq!(table.evaluate(&levels("X10")).unwrap().to_string(), "X");
+
+        table.add_row("--1", "0").unwrap();
+        assert_eq!(
+            table.expand().unwrap_err(),
+            "Row 5 contradicts row 3".to_string()
+        );
+        assert!(table.add_row("X00", "0").is_err());
+        assert!(table.add_row("00", "0").is_err());
+    }
+}
@@ -0,0 +1,562 @@
+//! JavaScript facing wrapper around `ComponentManager`.
+//!
+//! Values cross the boundary either as strings in the `Display` format of
+//! `Bits` (one character per bit, bit 0 first, e.g. `"10XZ"`) or as
+//! `Uint8Array`s holding one `LV` discriminant per bit.
+
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    bits::{Bits, LV},
+    expr::Equations,
+    history::Edit,
+    memfile,
+    minimize::{self, Mode},
+    notify::{Notification, Watch},
+    params::{Param, Params},
+    realtime::RealTimePacer,
+    run::{RunBudget, RunStatus},
+    synth::{Network, Style},
+    truth_table::TruthTable,
+    ComponentManager, PinType,
+};
+
+#[wasm_bindgen]
+pub struct Simulator {
+    cm: ComponentManager,
+    pacer: RealTimePacer,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_bits(value: &str) -> Result<Bits, String> {
+    value
+        .chars()
+        .map(|c| LV::from_char(c).ok_or(format!("'{}' is not a logic value", c)))
+        .collect()
+}
+
+fn levels_to_bits(levels: &[u8]) -> Result<Bits, String> {
+    levels
+        .iter()
+        .map(|l| match l {
+            0 => Ok(LV::H),
+            1 => Ok(LV::L),
+            2 => Ok(LV::X),
+            3 => Ok(LV::Z),
+            _ => Err(format!("{} is not a logic level", l)),
+        })
+        .collect()
+}
+
+fn bits_to_levels(bits: &Bits) -> Vec<u8> {
+    (0..bits.len()).map(|i| bits.get(i) as u8).collect()
+}
+
+fn style(nand: bool) -> Style {
+    if nand {
+        Style::Nand
+    } else {
+        Style::AndOrNot
+    }
+}
+
+/// Converts a JS number to an integer parameter, rejecting fractions, NaN
+/// and infinities instead of rounding them.
+fn int_param(key: &str, n: f64) -> Result<Param, String> {
+    if !n.is_finite() || n.fract() != 0.0 {
+        return Err(format!("Parameter {} must be an integer but is {}", key, n));
+    }
+    Ok(Param::Int(n as i64))
+}
+
+/// The kind of an edit and the ids it refers to, as passed to
+/// `on_topology_change` callbacks.
+fn edit_fields(edit: &Edit) -> (&'static str, Vec<(&'static str, usize)>) {
+    match edit {
+        Edit::CreateGate { gate_id } => ("create_gate", vec![("gate", *gate_id)]),
+        Edit::DeleteGate { gate_id } => ("delete_gate", vec![("gate", *gate_id)]),
+        Edit::CreateLump { lump_id } => ("create_lump", vec![("lump", *lump_id)]),
+        Edit::DeleteLump { lump_id } => ("delete_lump", vec![("lump", *lump_id)]),
+        Edit::Connect { pin_id, lump_id } => {
+            ("connect", vec![("pin", *pin_id), ("lump", *lump_id)])
+        }
+        Edit::Disconnect { pin_id, lump_id } => {
+            ("disconnect", vec![("pin", *pin_id), ("lump", *lump_id)])
+        }
+        Edit::SetParam { gate_id, .. } => ("set_param", vec![("gate", *gate_id)]),
+    }
+}
+
+fn edit_to_js(edit: &Edit) -> Object {
+    let object = Object::new();
+    let set = |key: &str, value: JsValue| {
+        let _ = Reflect::set(&object, &JsValue::from_str(key), &value);
+    };
+    let (kind, ids) = edit_fields(edit);
+    set("kind", JsValue::from_str(kind));
+    for (key, id) in ids {
+        set(key, JsValue::from(id as u32));
+    }
+    if let Edit::SetParam { name, .. } = edit {
+        set("name", JsValue::from_str(name));
+    }
+    object
+}
+
+fn call_js(callback: &Function, id: usize, value: &str, time: u64) {
+    let _ = callback.call3(
+        &JsValue::NULL,
+        &JsValue::from(id as u32),
+        &JsValue::from_str(value),
+        &JsValue::from(time as f64),
+    );
+}
+
+#[wasm_bindgen]
+impl Simulator {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Simulator {
+        Simulator {
+            cm: ComponentManager::new(),
+            pacer: RealTimePacer::new(1000.0),
+        }
+    }
+
+    // Circuit construction
+
+    pub fn create_gate(&mut self, name: &str) -> Result<usize, String> {
+        self.cm.create_gate(name)
+    }
+
+    /// Creates a gate configured by a plain object such as
+    /// `{ width: 8, label: "a" }`. Numbers become integer parameters and
+    /// must be whole, booleans and strings are passed through.
+    pub fn create_gate_with_params(
+        &mut self,
+        name: &str,
+        params: &Object,
+    ) -> Result<usize, String> {
+        let mut converted = Params::new();
+        for entry in Object::entries(params).iter() {
+            let entry = Array::from(&entry);
+            let key = entry.get(0).as_string().unwrap_or_default();
+            let value = entry.get(1);
+            let param = if let Some(b) = value.as_bool() {
+                Param::Bool(b)
+            } else if let Some(n) = value.as_f64() {
+                int_param(&key, n)?
+            } else if let Some(text) = value.as_string() {
+                Param::Text(text)
+            } else {
+                return Err(format!("Parameter {} has an unsupported type", key));
+            };
+            converted.set(&key, param);
+        }
+        self.cm.create_gate_with_params(name, converted)
+    }
+
+    pub fn find_gate_by_label(&self, label: &str) -> Option<usize> {
+        self.cm.find_gate_by_label(label)
+    }
+
+    pub fn delete_gate(&mut self, gate_id: usize) -> Result<(), String> {
+        self.cm.delete_gate(&gate_id)
+    }
+
+    pub fn create_lump(&mut self, width: usize) -> usize {
+        self.cm.create_lump(width)
+    }
+
+    pub fn delete_lump(&mut self, lump_id: usize) -> Result<(), String> {
+        self.cm.delete_lump(&lump_id)
+    }
+
+    pub fn connect(&mut self, pin_id: usize, lump_id: usize) -> Result<(), String> {
+        self.cm.connect_pin_to_lump(&pin_id, &lump_id)
+    }
+
+    pub fn disconnect(&mut self, pin_id: usize, lump_id: usize) {
+        self.cm.disconnect_pin_from_lump(&pin_id, &lump_id)
+    }
+
+    pub fn input_pins(&self, gate_id: usize) -> Result<Vec<usize>, String> {
+        self.cm.try_get_gate_pins(&gate_id, &PinType::IN)
+    }
+
+    pub fn output_pins(&self, gate_id: usize) -> Result<Vec<usize>, String> {
+        self.cm.try_get_gate_pins(&gate_id, &PinType::OUT)
+    }
+
+    pub fn inout_pins(&self, gate_id: usize) -> Result<Vec<usize>, String> {
+        self.cm.try_get_gate_pins(&gate_id, &PinType::INOUT)
+    }
+
+    pub fn begin_transaction(&mut self) {
+        self.cm.begin_transaction()
+    }
+
+    pub fn commit_transaction(&mut self) -> Result<(), String> {
+        self.cm.commit_transaction()
+    }
+
+    pub fn undo(&mut self) -> Result<bool, String> {
+        self.cm.undo()
+    }
+
+    pub fn redo(&mut self) -> Result<bool, String> {
+        self.cm.redo()
+    }
+
+    // Stimulus
+
+    /// Drives `pin_id` with a value string like `"10XZ"` after `delay`.
+    pub fn set_pin(&mut self, pin_id: usize, value: &str, delay: u64) -> Result<(), String> {
+        let bits = parse_bits(value)?;
+        self.schedule(pin_id, bits, delay)
+    }
+
+    /// Drives `pin_id` with one `LV` discriminant per bit after `delay`.
+    pub fn set_pin_levels(
+        &mut self,
+        pin_id: usize,
+        levels: &[u8],
+        delay: u64,
+    ) -> Result<(), String> {
+        let bits = levels_to_bits(levels)?;
+        self.schedule(pin_id, bits, delay)
+    }
+
+    fn schedule(&mut self, pin_id: usize, bits: Bits, delay: u64) -> Result<(), String> {
+        let width = self.cm.try_get_pin_value(&pin_id)?.len();
+        if width != bits.len() {
+            return Err(format!(
+                "Pin {} is {} bits wide but got {} bits",
+                pin_id,
+                width,
+                bits.len()
+            ));
+        }
+        self.cm.schedule_pin_update(delay, pin_id, bits);
+        Ok(())
+    }
+
+    /// Sets the value of an `input`, `switch` or `button` component.
+    pub fn set_input(&mut self, gate_id: usize, value: &str) -> Result<(), String> {
+        self.cm.set_input(&gate_id, parse_bits(value)?)
+    }
+
+    pub fn toggle_switch(&mut self, gate_id: usize) -> Result<(), String> {
+        self.cm.toggle_switch(&gate_id)
+    }
+
+    pub fn press_button(&mut self, gate_id: usize) -> Result<(), String> {
+        self.cm.press_button(&gate_id)
+    }
+
+    pub fn release_button(&mut self, gate_id: usize) -> Result<(), String> {
+        self.cm.release_button(&gate_id)
+    }
+
+    // Custom components
+
+    /// Adds a component defined by a truth table in CSV, see
+    /// `TruthTable::from_csv`, to be created by `name`.
+    pub fn register_truth_table(&mut self, name: &str, csv: &str) -> Result<(), String> {
+        let table = TruthTable::from_csv(csv)?;
+        self.cm.register_truth_table(name, &table)
+    }
+
+    /// Adds a component defined by Boolean equations that declare their
+    /// ports, see `Equations::parse`, to be created by `name`.
+    pub fn register_equations(&mut self, name: &str, text: &str) -> Result<(), String> {
+        let equations = Equations::parse(text)?;
+        self.cm.register_equations(name, &equations)
+    }
+
+    /// Builds Boolean equations from `and`, `or` and `not` gates, or only
+    /// `nand` gates, and returns the lumps of the inputs followed by those
+    /// of the outputs. With `minimize` every output bit becomes a minimized
+    /// sum of products first.
+    pub fn synthesize_equations(
+        &mut self,
+        text: &str,
+        nand: bool,
+        minimize: bool,
+    ) -> Result<Vec<usize>, String> {
+        let equations = Equations::parse(text)?;
+        let network = if minimize {
+            Network::from_equations_minimized(&equations, style(nand), Mode::Auto)?
+        } else {
+            Network::from_equations(&equations, style(nand))?
+        };
+        let circuit = network.build(&mut self.cm)?;
+        Ok([circuit.inputs, circuit.outputs].concat())
+    }
+
+    /// Like `synthesize_equations` for a truth table in CSV.
+    pub fn synthesize_truth_table(
+        &mut self,
+        csv: &str,
+        nand: bool,
+        minimize: bool,
+    ) -> Result<Vec<usize>, String> {
+        let table = TruthTable::from_csv(csv)?;
+        let network = if minimize {
+            Network::from_truth_table_minimized(&table, style(nand), Mode::Auto)?
+        } else {
+            Network::from_truth_table(&table, style(nand))?
+        };
+        let circuit = network.build(&mut self.cm)?;
+        Ok([circuit.inputs, circuit.outputs].concat())
+    }
+
+    /// Minimized equations for a truth table in CSV, as text for
+    /// `register_equations` or `synthesize_equations`.
+    pub fn minimize_truth_table(&self, csv: &str, exact: bool) -> Result<String, String> {
+        let table = TruthTable::from_csv(csv)?;
+        let mode = if exact { Mode::Exact } else { Mode::Auto };
+        minimize::table_equations(&table, mode)
+    }
+
+    // Memories
+
+    /// Loads the contents of a `rom`, `ram` or `sync_ram` from an image in
+    /// `format`, one of `"ihex"`, `"binary"` or `"hex"` (plain hex text).
+    pub fn load_memory(
+        &mut self,
+        gate_id: usize,
+        format: &str,
+        image: &[u8],
+    ) -> Result<(), String> {
+        let width = self.cm.read_memory(&gate_id, 0)?.len();
+        let size = self.cm.dump_memory(&gate_id)?.len();
+        let text = || String::from_utf8(image.to_vec()).map_err(|e| e.to_string());
+        let words = match format {
+            "ihex" => memfile::from_intel_hex(&text()?, width, size)?,
+            "binary" => memfile::from_binary(image, width, size)?,
+            "hex" => memfile::from_hex_text(&text()?, width, size)?,
+            _ => return Err(format!("Unknown memory image format {}", format)),
+        };
+        self.cm.load_memory(&gate_id, &words)
+    }
+
+    pub fn read_memory(&self, gate_id: usize, address: usize) -> Result<String, String> {
+        Ok(self.cm.read_memory(&gate_id, address)?.to_string())
+    }
+
+    pub fn write_memory(
+        &mut self,
+        gate_id: usize,
+        address: usize,
+        value: &str,
+    ) -> Result<(), String> {
+        self.cm.write_memory(&gate_id, address, parse_bits(value)?)
+    }
+
+    // Run control
+
+    pub fn current_time(&self) -> u64 {
+        self.cm.current_time()
+    }
+
+    pub fn has_pending_events(&self) -> bool {
+        self.cm.has_pending_events()
+    }
+
+    /// Processes the events of the current time step.
+    pub fn step(&mut self) {
+        self.cm.process_pin_events()
+    }
+
+    /// Steps until no events are left or `max_steps` is reached. Returns
+    /// whether events are still pending.
+    pub fn run(&mut self, max_steps: u32) -> bool {
+        for _ in 0..max_steps {
+            if !self.cm.has_pending_events() {
+                break;
+            }
+            self.cm.process_pin_events();
+        }
+        self.cm.has_pending_events()
+    }
+
+    /// Runs for at most `max_events` events and `max_micros` microseconds of
+    /// wall time, a limit of 0 meaning unlimited. Returns whether the run was
+    /// suspended with events still pending, e.g. to continue in the next
+    /// animation frame.
+    pub fn run_slice(&mut self, max_events: u32, max_micros: u32) -> bool {
+        let budget = RunBudget {
+            max_events: (max_events > 0).then_some(max_events as usize),
+            max_micros: (max_micros > 0).then_some(max_micros as u64),
+            ..Default::default()
+        };
+        self.cm.run_budgeted(&budget) == RunStatus::Suspended
+    }
+
+    // Real time
+
+    /// Starts or resumes advancing simulation time with the wall clock.
+    pub fn resume_realtime(&mut self) {
+        self.pacer.resume(&self.cm)
+    }
+
+    pub fn pause_realtime(&mut self) {
+        self.pacer.pause()
+    }
+
+    pub fn is_realtime_running(&self) -> bool {
+        self.pacer.is_running()
+    }
+
+    /// Sets how many simulation time units pass per wall clock second.
+    pub fn set_realtime_speed(&mut self, sim_units_per_second: f64) {
+        self.pacer.set_speed(&self.cm, sim_units_per_second)
+    }
+
+    /// Catches the simulation up with the wall clock, meant to be called once
+    /// per animation frame. Limits are the same as for `run_slice`; returns
+    /// whether the run was cut short and lags behind.
+    pub fn tick_realtime(&mut self, max_events: u32, max_micros: u32) -> bool {
+        let budget = RunBudget {
+            max_events: (max_events > 0).then_some(max_events as usize),
+            max_micros: (max_micros > 0).then_some(max_micros as u64),
+            ..Default::default()
+        };
+        self.pacer.advance(&mut self.cm, &budget) == RunStatus::Suspended
+    }
+
+    // Readback
+
+    pub fn pin_value(&self, pin_id: usize) -> Result<String, String> {
+        Ok(self.cm.try_get_pin_value(&pin_id)?.to_string())
+    }
+
+    pub fn lump_value(&self, lump_id: usize) -> Result<String, String> {
+        Ok(self.cm.try_get_lump_value(&lump_id)?.to_string())
+    }
+
+    pub fn pin_levels(&self, pin_id: usize) -> Result<Vec<u8>, String> {
+        Ok(bits_to_levels(&self.cm.try_get_pin_value(&pin_id)?))
+    }
+
+    pub fn lump_levels(&self, lump_id: usize) -> Result<Vec<u8>, String> {
+        Ok(bits_to_levels(&self.cm.try_get_lump_value(&lump_id)?))
+    }
+
+    /// Indices of the bits where equally strong drivers fight.
+    pub fn lump_conflicts(&self, lump_id: usize) -> Result<Vec<u32>, String> {
+        let conflicts = self.cm.lump_conflicts(&lump_id)?;
+        Ok(conflicts.into_iter().map(|i| i as u32).collect())
+    }
+
+    /// Reads the value arriving at a `probe` or `led` component.
+    pub fn read_output(&self, gate_id: usize) -> Result<String, String> {
+        Ok(self.cm.read_output(&gate_id)?.to_string())
+    }
+
+    // Change callbacks
+
+    /// Calls `callback(pin_id, value, time)` whenever the pin changes.
+    pub fn on_pin_change(&self, pin_id: usize, callback: Function) -> usize {
+        self.cm.subscribe(Watch::Pin(pin_id), move |n| {
+            if let Notification::PinChanged {
+                pin_id,
+                time,
+                value,
+            } = n
+            {
+                call_js(&callback, *pin_id, &value.to_string(), *time);
+            }
+        })
+    }
+
+    /// Calls `callback(lump_id, value, time)` whenever the lump changes.
+    pub fn on_lump_change(&self, lump_id: usize, callback: Function) -> usize {
+        self.cm.subscribe(Watch::Lump(lump_id), move |n| {
+            if let Notification::LumpChanged {
+                lump_id,
+                time,
+                value,
+            } = n
+            {
+                call_js(&callback, *lump_id, &value.to_string(), *time);
+            }
+        })
+    }
+
+    /// Calls `callback(change)` for every structural change, with `change`
+    /// an object like `{ kind: "connect", pin: 4, lump: 7 }`. `kind` is one
+    /// of `create_gate`, `delete_gate`, `create_lump`, `delete_lump`,
+    /// `connect`, `disconnect` or `set_param`, which also has the parameter
+    /// `name`.
+    pub fn on_topology_change(&self, callback: Function) -> usize {
+        self.cm.subscribe(Watch::Topology, move |n| {
+            if let Notification::Topology(edit) = n {
+                let _ = callback.call1(&JsValue::NULL, &edit_to_js(edit));
+            }
+        })
+    }
+
+    pub fn unsubscribe(&self, subscription_id: usize) -> bool {
+        self.cm.unsubscribe(subscription_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_fields, int_param, Simulator};
+    use crate::{history::Edit, params::Param};
+
+    #[test]
+    fn test_drive_and_read_back() {
+        let mut sim = Simulator::new();
+        let and = sim.create_gate("and").unwrap();
+        let lump = sim.create_lump(1);
+        let out = sim.output_pins(and).unwrap()[0];
+        sim.connect(out, lump).unwrap();
+        let inputs = sim.input_pins(and).unwrap();
+        sim.set_pin(inputs[0], "1", 0).unwrap();
+        sim.set_pin_levels(inputs[1], &[0], 0).unwrap();
+        assert!(sim.set_pin(inputs[1], "11", 0).is_err());
+        assert!(sim.set_pin(inputs[1], "q", 0).is_err());
+
+        assert!(!sim.run(10));
+        assert_eq!(sim.lump_value(lump).unwrap(), "1");
+        assert_eq!(sim.pin_levels(out).unwrap(), vec![0]);
+        assert!(sim.pin_value(12345).is_err());
+    }
+
+    #[test]
+    fn test_int_params() {
+        assert_eq!(int_param("width", 8.0), Ok(Param::Int(8)));
+        assert_eq!(int_param("delay", -3.0), Ok(Param::Int(-3)));
+        assert!(int_param("width", 2.5).is_err());
+        assert!(int_param("width", f64::NAN).is_err());
+        assert!(int_param("width", f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn test_edit_fields() {
+        let edit = Edit::Connect {
+            pin_id: 4,
+            lump_id: 7,
+        };
+        assert_eq!(
+            edit_fields(&edit),
+            ("connect", vec![("pin", 4), ("lump", 7)])
+        );
+        let edit = Edit::SetParam {
+            gate_id: 2,
+            name: "width".to_string(),
+            old: None,
+            new: Some(Param::Int(4)),
+        };
+        assert_eq!(edit_fields(&edit), ("set_param", vec![("gate", 2)]));
+    }
+}